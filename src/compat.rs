@@ -0,0 +1,32 @@
+//! Panicking versions of the core functions, kept for existing callers.
+//!
+//! Before 0.2 the crate root exported panicking `pow_mod`,
+//! `legendre_symbol`, `tonelli_shanks` and `square_roots`. Those names now
+//! return `Result<_, TonelliError>` instead, which is a breaking change.
+//! The functions re-exported here keep the old `u64` signatures, whatever
+//! the `Int` width selected by the crate features, and panic when given an
+//! invalid modulus (zero, or even and not 2):
+//!
+//! | Panicking                      | Result-returning                       |
+//! |--------------------------------|----------------------------------------|
+//! | `compat::pow_mod`              | `pow_mod`                              |
+//! | `compat::legendre_symbol`      | `legendre_symbol`                      |
+//! | `compat::tonelli_shanks`       | `tonelli_shanks`                       |
+//! | `compat::square_roots`         | `square_roots`                         |
+//!
+//! To migrate, either import from `tonelli_rs::compat` to keep the old
+//! behaviour unchanged, or handle the `TonelliError` returned by the
+//! function at the crate root.
+//!
+//! # Examples
+//! ```
+//! use tonelli_rs::compat;
+//!
+//! assert_eq!(compat::tonelli_shanks(2, 7), Some(3));
+//! assert_eq!(tonelli_rs::tonelli_shanks(2, 7), Ok(Some(3)));
+//! ```
+
+pub use crate::{
+    find_quadratic_non_residue, legendre_symbol_u64 as legendre_symbol, pow_mod_u64 as pow_mod,
+    square_roots_u64 as square_roots, tonelli_shanks_u64 as tonelli_shanks,
+};
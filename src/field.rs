@@ -0,0 +1,531 @@
+//! Precomputed context for repeated square roots modulo a fixed prime.
+
+use crate::{
+    TonelliError, Trace, canonical_root, check_modulus, find_quadratic_non_residue,
+    legendre_symbol_u64, mod_inverse, modular_sqrt_any, mul_mod, pow_mod_u64, split_two_adic,
+    tonelli_shanks_loop,
+};
+
+/// A prime modulus together with its precomputed Tonelli-Shanks parameters
+///
+/// Computing a square root modulo p needs p - 1 = 2^s · q and a quadratic
+/// non-residue z, which only depend on p. Building a `PrimeField` once and
+/// reusing it skips that setup, in particular the non-residue search, for
+/// every later root.
+///
+/// A `PrimeField` is a handful of integers, the s + 1 entries of its tower
+/// of 2-power roots of unity and the s entries of the inverse tower, so it
+/// is cheap to clone. It has no interior mutability and every query takes
+/// `&self`, which makes it `Send + Sync`: many threads can share one
+/// context, for example through an `Arc`, without any locking.
+/// `CachedPrimeField` adds a Legendre symbol cache on top for callers that
+/// repeat queries.
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::PrimeField;
+///
+/// let field = PrimeField::new(17);
+/// assert_eq!(field.sqrt(2), Some(6));
+/// assert_eq!(field.sqrt(3), None);
+/// ```
+///
+/// Sharing one context between threads:
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use tonelli_rs::PrimeField;
+///
+/// let field = Arc::new(PrimeField::new(1000000009));
+/// let handles: Vec<_> = (0..4)
+///     .map(|i| {
+///         let field = Arc::clone(&field);
+///         thread::spawn(move || field.sqrt(4 * (i + 1) * (i + 1)))
+///     })
+///     .collect();
+///
+/// for (i, handle) in handles.into_iter().enumerate() {
+///     assert_eq!(handle.join().unwrap(), Some(2 * (i as u64 + 1)));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeField {
+    p: u64,
+    s: u32,
+    q: u64,
+    z: u64,
+    c: u64,
+    /// `unity_tower[j]` is a primitive 2^j-th root of unity, for j = 0..=s
+    unity_tower: Vec<u64>,
+    /// `inverse_tower[j]` is c^(-2^j), for j = 0..s
+    inverse_tower: Vec<u64>,
+    /// (p + 1) / 2, the inverse of 2 and the first non-canonical root
+    inv_two: u64,
+}
+
+/// Number of slots in the Legendre symbol cache of a `CachedPrimeField`
+pub const LEGENDRE_CACHE_SIZE: usize = 16;
+
+impl PrimeField {
+    /// Precomputes the square-root parameters for the prime p
+    ///
+    /// # Arguments
+    /// * `p` - The prime modulus (2 or an odd prime)
+    ///
+    /// # Returns
+    /// A `PrimeField` ready to compute square roots modulo p
+    pub fn new(p: u64) -> Self {
+        let z = match p % 4 {
+            1 => find_quadratic_non_residue(p),
+            // For p ≡ 3 (mod 4) the fast path never needs z, but -1 is a
+            // non-residue, so it is still the true value
+            3 => p - 1,
+            _ => 1,
+        };
+
+        Self::from_parts_unchecked(p, z)
+    }
+
+    /// Builds a `PrimeField` from p and a known quadratic non-residue z
+    ///
+    /// Skips the non-residue search of `new`, e.g. for a z stored alongside
+    /// p. The result is checked with `validate`, so a z that is not a
+    /// non-residue is rejected up front.
+    ///
+    /// # Arguments
+    /// * `p` - The prime modulus (2 or an odd prime)
+    /// * `z` - A quadratic non-residue modulo p (ignored for p = 2)
+    ///
+    /// # Returns
+    /// * `Ok(field)` if p is a valid modulus and z a non-residue modulo p
+    /// * `Err(TonelliError)` otherwise
+    ///
+    /// # Examples
+    /// ```
+    /// use tonelli_rs::{PrimeField, TonelliError};
+    ///
+    /// assert_eq!(PrimeField::from_parts(17, 3), Ok(PrimeField::new(17)));
+    /// assert_eq!(PrimeField::from_parts(17, 2), Err(TonelliError::InvalidField));
+    /// ```
+    pub fn from_parts(p: u64, z: u64) -> Result<Self, TonelliError> {
+        check_modulus(p)?;
+        if p == 1 {
+            return Err(TonelliError::InvalidField);
+        }
+
+        let field = Self::from_parts_unchecked(p, z);
+        field.validate()?;
+        Ok(field)
+    }
+
+    /// Builds a `PrimeField` from p and z without checking that z is a non-residue
+    ///
+    /// If z is not a non-residue the cached c = z^q does not generate the
+    /// 2-power roots of unity, so the context is inconsistent. `validate`
+    /// detects this.
+    ///
+    /// # Arguments
+    /// * `p` - The prime modulus (2 or an odd prime)
+    /// * `z` - A quadratic non-residue modulo p (ignored for p = 2)
+    ///
+    /// # Returns
+    /// A `PrimeField` whose cached parameters are all derived from p and z
+    ///
+    /// # Panics
+    /// Panics if p is 0 or even and not 2
+    pub fn from_parts_unchecked(p: u64, z: u64) -> Self {
+        if p == 2 {
+            return PrimeField {
+                p,
+                s: 0,
+                q: 1,
+                z: 1,
+                c: 1,
+                unity_tower: vec![1],
+                inverse_tower: Vec::new(),
+                inv_two: 1,
+            };
+        }
+
+        if p.is_multiple_of(2) {
+            panic!();
+        }
+
+        let (s, q) = split_two_adic(p);
+        let c = pow_mod_u64(z, q, p);
+
+        // c = z^q has order exactly 2^s, so squaring it s - j times leaves a
+        // primitive 2^j-th root of unity
+        let mut unity_tower = vec![c];
+        for _ in 0..s {
+            let last = unity_tower[unity_tower.len() - 1];
+            unity_tower.push(mul_mod(last, last, p));
+        }
+        unity_tower.reverse();
+
+        // c^(-1) = c^(2^s - 1) is the product of c^(2^j) for j < s, and
+        // squaring it walks down the inverse tower
+        let c_inverse = unity_tower[1..]
+            .iter()
+            .fold(1, |acc, &x| mul_mod(acc, x, p));
+        let mut inverse_tower = vec![c_inverse];
+        for _ in 1..s {
+            let last = inverse_tower[inverse_tower.len() - 1];
+            inverse_tower.push(mul_mod(last, last, p));
+        }
+
+        PrimeField {
+            p,
+            s,
+            q,
+            z,
+            c,
+            unity_tower,
+            inverse_tower,
+            inv_two: p / 2 + 1,
+        }
+    }
+
+    /// Checks that the cached parameters are consistent with the modulus
+    ///
+    /// Recomputes p - 1 = 2^s · q, checks that z is a quadratic non-residue
+    /// and that c = z^q, and rebuilds both towers of roots of unity and the
+    /// inverse of 2. For a prime p, a context from `new` or `from_parts`
+    /// always passes, while one from `from_parts_unchecked` may not.
+    ///
+    /// # Returns
+    /// * `Ok(())` if every cached parameter matches p
+    /// * `Err(TonelliError::ModulusZero)` or `Err(TonelliError::ModulusEven)`
+    ///   if p itself is invalid
+    /// * `Err(TonelliError::InvalidField)` if a parameter is inconsistent
+    ///
+    /// # Examples
+    /// ```
+    /// use tonelli_rs::{PrimeField, TonelliError};
+    ///
+    /// assert_eq!(PrimeField::new(17).validate(), Ok(()));
+    /// // 2 ≡ 6² (mod 17) is a residue
+    /// let corrupted = PrimeField::from_parts_unchecked(17, 2);
+    /// assert_eq!(corrupted.validate(), Err(TonelliError::InvalidField));
+    /// ```
+    pub fn validate(&self) -> Result<(), TonelliError> {
+        check_modulus(self.p)?;
+        if self.p == 1 || (self.p != 2 && legendre_symbol_u64(self.z, self.p) != -1) {
+            return Err(TonelliError::InvalidField);
+        }
+
+        if *self != Self::from_parts_unchecked(self.p, self.z) {
+            return Err(TonelliError::InvalidField);
+        }
+        Ok(())
+    }
+
+    /// Returns the prime modulus p
+    pub fn modulus(&self) -> u64 {
+        self.p
+    }
+
+    /// Returns the cached tower of 2-power roots of unity
+    ///
+    /// Entry j is a primitive 2^j-th root of unity for j = 0..=s, where
+    /// p - 1 = 2^s · q, so the tower starts `[1, p - 1, ...]`.
+    ///
+    /// # Examples
+    /// ```
+    /// use tonelli_rs::PrimeField;
+    ///
+    /// // 16 = 2^4, and 3 is a non-residue modulo 17
+    /// assert_eq!(PrimeField::new(17).unity_tower(), &[1, 16, 13, 9, 3]);
+    /// ```
+    pub fn unity_tower(&self) -> &[u64] {
+        &self.unity_tower
+    }
+
+    /// Returns the cached inverse of 2 modulo p, or `None` for p = 2
+    ///
+    /// # Examples
+    /// ```
+    /// use tonelli_rs::PrimeField;
+    ///
+    /// assert_eq!(PrimeField::new(17).inv_two(), Some(9));
+    /// assert_eq!(PrimeField::new(2).inv_two(), None);
+    /// ```
+    pub fn inv_two(&self) -> Option<u64> {
+        (self.p != 2).then_some(self.inv_two)
+    }
+
+    /// Computes a square root of n modulo p using the cached parameters
+    ///
+    /// # Arguments
+    /// * `n` - The number to find the square root of
+    ///
+    /// # Returns
+    /// The same canonical root as `tonelli_shanks_u64(n, p)`
+    pub fn sqrt(&self, n: u64) -> Option<u64> {
+        self.sqrt_canonical(n)
+    }
+
+    /// Computes the canonical square root of n modulo p using only cached constants
+    ///
+    /// The canonical root r ≤ p - r is exactly the one below (p + 1) / 2,
+    /// the cached inverse of 2, so picking it costs a single comparison.
+    ///
+    /// # Arguments
+    /// * `n` - The number to find the square root of
+    ///
+    /// # Returns
+    /// * `Some(r)` with r² ≡ n (mod p) and r ≤ p - r
+    /// * `None` if n is not a quadratic residue modulo p
+    ///
+    /// # Examples
+    /// ```
+    /// use tonelli_rs::PrimeField;
+    ///
+    /// let field = PrimeField::new(17);
+    /// assert_eq!(field.sqrt_canonical(2), Some(6)); // 6² ≡ 11² ≡ 2 (mod 17)
+    /// assert_eq!(field.sqrt_canonical(3), None);
+    /// ```
+    pub fn sqrt_canonical(&self, n: u64) -> Option<u64> {
+        let p = self.p;
+        if p == 2 {
+            return Some(n % 2);
+        }
+
+        let n_mod_p = n % p;
+        if n_mod_p == 0 {
+            return Some(0);
+        }
+
+        if legendre_symbol_u64(n_mod_p, p) != 1 {
+            return None;
+        }
+
+        self.sqrt_of_residue(n_mod_p)
+    }
+
+    /// Computes the canonical square root of a reduced nonzero quadratic residue
+    fn sqrt_of_residue(&self, n_mod_p: u64) -> Option<u64> {
+        let p = self.p;
+        let r = if p % 4 == 3 {
+            pow_mod_u64(n_mod_p, p / 4 + 1, p)
+        } else {
+            // The loop only fails, raising the fallback flag, for a bad context
+            match tonelli_shanks_loop::<false>(
+                n_mod_p,
+                p,
+                self.s,
+                self.q,
+                self.c,
+                &mut Trace::new(),
+            ) {
+                Some(r) => r,
+                None => modular_sqrt_any(n_mod_p, p)?,
+            }
+        };
+
+        Some(if r < self.inv_two { r } else { p - r })
+    }
+
+    /// Computes a 2^k-th root of n modulo p using the cached tower
+    ///
+    /// Writes n = (n^u)^(2^k) · w^(-m) with u the inverse of 2^k modulo q and
+    /// w = n^q in the subgroup of order 2^s. The logarithm of w in that
+    /// subgroup is read off bit by bit, and each set bit is cleared with one
+    /// multiplication by the cached c^(-2^i). The non-residue search and the
+    /// inverses of the tower are shared with `sqrt` and paid once per field.
+    ///
+    /// # Arguments
+    /// * `n` - The number to take the root of
+    /// * `k` - The exponent of two, so the root r satisfies r^(2^k) ≡ n (mod p)
+    ///
+    /// # Returns
+    /// * `Some(r)` with r^(2^k) ≡ n (mod p), the smaller of r and p - r for k ≥ 1
+    /// * `None` if n is not a 2^k-th power modulo p
+    ///
+    /// # Examples
+    /// ```
+    /// use tonelli_rs::{PrimeField, pow_mod_u64};
+    ///
+    /// let field = PrimeField::new(17);
+    /// assert_eq!(field.nth_power_of_two_root(2, 1), field.sqrt(2));
+    ///
+    /// let r = field.nth_power_of_two_root(16, 2).unwrap();
+    /// assert_eq!(pow_mod_u64(r, 4, 17), 16);
+    /// assert_eq!(field.nth_power_of_two_root(2, 3), None);
+    /// ```
+    pub fn nth_power_of_two_root(&self, n: u64, k: u32) -> Option<u64> {
+        let (p, s, q) = (self.p, self.s, self.q);
+        let n = n % p;
+        if p == 2 || n == 0 || k == 0 {
+            return Some(n);
+        }
+
+        // u · 2^k ≡ 1 (mod q), and -m = (1 - u · 2^k) / q is only needed mod 2^s
+        let u = mod_inverse(pow_mod_u64(2, k as u64, q), q)?;
+        let mut q_inverse: u64 = q;
+        for _ in 0..6 {
+            q_inverse = q_inverse.wrapping_mul(2u64.wrapping_sub(q.wrapping_mul(q_inverse)));
+        }
+        let shifted = if k < 64 { u.wrapping_shl(k) } else { 0 };
+        let minus_m = 1u64.wrapping_sub(shifted).wrapping_mul(q_inverse);
+
+        // Logarithm of w = n^q to the base c = unity_tower[s], one bit at a time
+        let mut t = pow_mod_u64(n, q, p);
+        let mut log = 0u64;
+        for i in 0..s {
+            let mut x = t;
+            for _ in i + 1..s {
+                x = mul_mod(x, x, p);
+            }
+            if x != 1 {
+                log |= 1 << i;
+                t = mul_mod(t, self.inverse_tower[i as usize], p);
+            }
+        }
+
+        let mask = (1u64 << s) - 1;
+        let e = log.wrapping_mul(minus_m) & mask;
+        let y = if k < s {
+            if e & ((1 << k) - 1) != 0 {
+                return None;
+            }
+            pow_mod_u64(self.c, e >> k, p)
+        } else if e == 0 {
+            1
+        } else {
+            return None;
+        };
+
+        Some(canonical_root(mul_mod(pow_mod_u64(n, u, p), y, p), p))
+    }
+}
+
+/// A `PrimeField` with a cache of recent Legendre symbols
+///
+/// The cache has `LEGENDRE_CACHE_SIZE` slots and is direct-mapped: n mod p
+/// always goes to slot (n mod p) mod `LEGENDRE_CACHE_SIZE`, evicting
+/// whatever was stored there. Repeating a query for the same n skips the
+/// residuosity check, and non-residues are answered from the cache alone.
+/// The slots add 384 bytes to the field and `sqrt` needs `&mut self`, so
+/// the cache is opt-in rather than part of every `PrimeField`.
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{CachedPrimeField, PrimeField};
+///
+/// let mut field = CachedPrimeField::new(PrimeField::new(17));
+/// assert_eq!(field.sqrt(2), Some(6));
+/// assert_eq!(field.sqrt(2), Some(6)); // Legendre symbol from the cache
+/// assert_eq!(field.sqrt(3), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedPrimeField {
+    field: PrimeField,
+    /// Recent (n mod p, Legendre symbol) pairs, filled by `sqrt`
+    legendre_cache: [Option<(u64, i32)>; LEGENDRE_CACHE_SIZE],
+}
+
+impl CachedPrimeField {
+    /// Wraps a `PrimeField` with an empty cache
+    ///
+    /// # Arguments
+    /// * `field` - The precomputed context to answer queries with
+    ///
+    /// # Returns
+    /// A `CachedPrimeField` whose cache fills as queries come in
+    pub fn new(field: PrimeField) -> Self {
+        CachedPrimeField {
+            field,
+            legendre_cache: [None; LEGENDRE_CACHE_SIZE],
+        }
+    }
+
+    /// Returns the wrapped `PrimeField`
+    pub fn field(&self) -> &PrimeField {
+        &self.field
+    }
+
+    /// Computes the canonical square root of n, remembering its Legendre symbol
+    ///
+    /// # Arguments
+    /// * `n` - The number to find the square root of
+    ///
+    /// # Returns
+    /// The same canonical root as `PrimeField::sqrt(n)`
+    pub fn sqrt(&mut self, n: u64) -> Option<u64> {
+        let p = self.field.p;
+        if p == 2 {
+            return Some(n % 2);
+        }
+
+        let n_mod_p = n % p;
+        if n_mod_p == 0 {
+            return Some(0);
+        }
+
+        let slot = &mut self.legendre_cache[(n_mod_p % LEGENDRE_CACHE_SIZE as u64) as usize];
+        let symbol = match *slot {
+            Some((cached, symbol)) if cached == n_mod_p => symbol,
+            _ => {
+                let symbol = legendre_symbol_u64(n_mod_p, p);
+                *slot = Some((n_mod_p, symbol));
+                symbol
+            }
+        };
+
+        if symbol != 1 {
+            return None;
+        }
+
+        self.field.sqrt_of_residue(n_mod_p)
+    }
+}
+
+/// Computes a square root of n in each of several prebuilt fields
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `fields` - The precomputed contexts, for example held for a server's lifetime
+///
+/// # Returns
+/// `field.sqrt(n)` for each field, in the order of `fields`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{PrimeField, sqrt_across_fields};
+///
+/// let fields = [PrimeField::new(7), PrimeField::new(17), PrimeField::new(5)];
+/// assert_eq!(sqrt_across_fields(2, &fields), vec![Some(3), Some(6), None]);
+/// ```
+pub fn sqrt_across_fields(n: u64, fields: &[PrimeField]) -> Vec<Option<u64>> {
+    fields.iter().map(|field| field.sqrt(n)).collect()
+}
+
+/// Extension trait lifting an iterator of numbers into their square roots mod p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::SqrtIterExt;
+///
+/// let roots: Vec<u64> = (1..7).sqrt_mod(7).flatten().collect();
+/// assert_eq!(roots, vec![1, 3, 2]);
+/// ```
+pub trait SqrtIterExt: Iterator<Item = u64> + Sized {
+    /// Maps every item n to its canonical square root modulo p
+    ///
+    /// A single `PrimeField` is built up front and reused for every item.
+    ///
+    /// # Arguments
+    /// * `p` - The prime modulus
+    ///
+    /// # Returns
+    /// An iterator yielding `tonelli_shanks_u64(n, p)` for each item n
+    fn sqrt_mod(self, p: u64) -> impl Iterator<Item = Option<u64>>;
+}
+
+impl<I: Iterator<Item = u64>> SqrtIterExt for I {
+    fn sqrt_mod(self, p: u64) -> impl Iterator<Item = Option<u64>> {
+        let field = PrimeField::new(p);
+        self.map(move |n| field.sqrt(n))
+    }
+}
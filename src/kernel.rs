@@ -0,0 +1,196 @@
+//! The Tonelli-Shanks kernel, written once for every integer width.
+//!
+//! `tonelli_kernel!` expands to the `COUNT`-generic `const fn` core for one
+//! unsigned type. It is invoked at the crate root for `u64`, in `wide` for
+//! `u128` and in `narrow` for `u32`. Each invocation site supplies its own
+//! `const fn mul_mod` for the type, which is the only part that differs
+//! between widths, and has `OpCount` and `Trace` in scope.
+
+/// Expands to the Tonelli-Shanks core functions for the given integer type
+macro_rules! tonelli_kernel {
+    ($int:ty) => {
+        /// Returns the canonical (smaller) one of the two square roots r and p - r
+        const fn canonical_root(r: $int, p: $int) -> $int {
+            if r <= p - r { r } else { p - r }
+        }
+
+        /// Computes x^n mod p, tallying the field operations into `ops` when `COUNT` is set
+        ///
+        /// `COUNT` is a const parameter so the plain functions, which pass `false`,
+        /// compile the tallying out instead of paying for it on every call.
+        const fn pow_mod_counted<const COUNT: bool>(
+            mut x: $int,
+            mut n: $int,
+            p: $int,
+            ops: &mut OpCount,
+        ) -> $int {
+            if p == 0 {
+                panic!();
+            }
+
+            let mut result = 1 % p;
+            x %= p;
+
+            while n > 0 {
+                if n & 1 == 1 {
+                    result = mul_mod(result, x, p);
+                    if COUNT {
+                        ops.mults += 1;
+                    }
+                }
+                x = mul_mod(x, x, p);
+                if COUNT {
+                    ops.sqrs += 1;
+                }
+                n >>= 1;
+            }
+
+            result
+        }
+
+        /// Computes the Legendre symbol (a/p), tallying the field operations into `ops` when `COUNT` is set
+        const fn legendre_symbol_counted<const COUNT: bool>(
+            a: $int,
+            p: $int,
+            ops: &mut OpCount,
+        ) -> i32 {
+            let a_mod_p = a % p;
+            if a_mod_p == 0 {
+                return 0;
+            }
+
+            let result = pow_mod_counted::<COUNT>(a_mod_p, (p - 1) / 2, p, ops);
+            if result == 1 {
+                1
+            } else if result == p - 1 {
+                -1
+            } else {
+                0
+            }
+        }
+
+        /// Finds the first quadratic non-residue modulo p, tallying the field operations into `ops` when `COUNT` is set
+        ///
+        /// Returns `None` if Euler's criterion never yields -1, which happens for
+        /// some composite moduli such as 9.
+        const fn find_quadratic_non_residue_counted<const COUNT: bool>(
+            p: $int,
+            ops: &mut OpCount,
+        ) -> Option<$int> {
+            let mut z = 2;
+            while z < p {
+                if legendre_symbol_counted::<COUNT>(z, p, ops) == -1 {
+                    return Some(z);
+                }
+                z += 1;
+            }
+            None
+        }
+
+        /// Writes p - 1 as 2^s · q with q odd, returning (s, q)
+        const fn split_two_adic(p: $int) -> (u32, $int) {
+            let q = (p - 1) >> (p - 1).trailing_zeros();
+            ((p - 1).trailing_zeros(), q)
+        }
+
+        /// Runs Tonelli-Shanks without the fallback, usable in a const context
+        ///
+        /// Returns `None` and raises `trace.fallback` if the main loop fails or
+        /// no non-residue exists, so the runtime callers can fall back to
+        /// factoring p while `sqrt_const` gives up.
+        const fn tonelli_shanks_core<const COUNT: bool>(
+            n: $int,
+            p: $int,
+            trace: &mut Trace,
+        ) -> Option<$int> {
+            let ops = &mut trace.ops;
+
+            if p == 2 {
+                return Some(n % 2);
+            }
+
+            if p.is_multiple_of(2) {
+                panic!();
+            }
+
+            let n_mod_p = n % p;
+            if n_mod_p == 0 {
+                return Some(0);
+            }
+
+            if legendre_symbol_counted::<COUNT>(n_mod_p, p, ops) != 1 {
+                return None;
+            }
+
+            if p % 4 == 3 {
+                let r = pow_mod_counted::<COUNT>(n_mod_p, p / 4 + 1, p, ops);
+                return Some(r);
+            }
+
+            let (s, q) = split_two_adic(p);
+            let Some(z) = find_quadratic_non_residue_counted::<COUNT>(p, ops) else {
+                trace.fallback = true;
+                return None;
+            };
+            let c = pow_mod_counted::<COUNT>(z, q, p, ops);
+            if COUNT {
+                // The search examines 2, 3, ..., z
+                trace.nr_candidates = (z - 1) as u32;
+            }
+            tonelli_shanks_loop::<COUNT>(n_mod_p, p, s, q, c, trace)
+        }
+
+        /// Runs the main Tonelli-Shanks loop for a reduced quadratic residue n
+        ///
+        /// Takes p - 1 = 2^s · q and c = z^q for a quadratic non-residue z, so the
+        /// setup can be shared with precomputed contexts. If the loop runs out of
+        /// order it raises `trace.fallback` and returns `None`.
+        const fn tonelli_shanks_loop<const COUNT: bool>(
+            n_mod_p: $int,
+            p: $int,
+            s: u32,
+            q: $int,
+            c: $int,
+            trace: &mut Trace,
+        ) -> Option<$int> {
+            let mut c = c;
+            let mut r = pow_mod_counted::<COUNT>(n_mod_p, q.div_ceil(2), p, &mut trace.ops);
+            let mut t = pow_mod_counted::<COUNT>(n_mod_p, q, p, &mut trace.ops);
+            let mut m = s;
+            trace.push_m(m);
+
+            while t != 1 {
+                let mut tt = t;
+                let mut i = 0;
+
+                while tt != 1 {
+                    tt = mul_mod(tt, tt, p);
+                    if COUNT {
+                        trace.ops.sqrs += 1;
+                    }
+                    i += 1;
+                    if i == m {
+                        trace.fallback = true;
+                        return None;
+                    }
+                }
+
+                let b = pow_mod_counted::<COUNT>(c, 1 << (m - i - 1), p, &mut trace.ops);
+                let b2 = mul_mod(b, b, p);
+                r = mul_mod(r, b, p);
+                t = mul_mod(t, b2, p);
+                if COUNT {
+                    trace.ops.sqrs += 1;
+                    trace.ops.mults += 2;
+                }
+                c = b2;
+                m = i;
+                trace.push_m(m);
+            }
+
+            Some(r)
+        }
+    };
+}
+
+pub(crate) use tonelli_kernel;
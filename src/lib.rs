@@ -2,6 +2,154 @@
 //!
 //! This library provides functions to compute square roots in the finite field Z/pZ
 //! where p is an odd prime number.
+//!
+//! Every nonzero quadratic residue has two square roots, r and p - r. All
+//! functions returning a single root return the canonical one, i.e. the
+//! smaller of the two, so the result does not depend on which algorithm ran
+//! or on any of its internal choices. This is part of the crate's stability
+//! guarantee and is pinned by test vectors.
+//!
+//! # Features
+//!
+//! * `bench-api` - Enables `bench_report` for timing the `u64` and `u128`
+//!   code paths from within a program.
+//! * `simd` - Enables `legendre_symbols_simd`, which uses the unstable
+//!   `std::simd` module and therefore requires a nightly compiler.
+//! * `width-u64` (default) and `width-u128` - Select the integer type `Int`
+//!   used by `pow_mod`, `legendre_symbol`, `tonelli_shanks` and
+//!   `square_roots`. The features are additive: if both are enabled, for
+//!   example because two dependents ask for different widths, `width-u128`
+//!   wins. `u128` supports larger primes, but its modular multiplication
+//!   cannot use a wider native type and is several times slower. The
+//!   explicitly sized `*_u64` and `*_u128` versions, as well as
+//!   `tonelli_shanks_u32`, are always available, and every other function
+//!   in the crate works on `u64`.
+//!
+//! # Panics
+//!
+//! Most functions panic on a modulus they cannot work with, such as zero or
+//! an even number other than 2, in the same way integer division panics on
+//! zero. The following functions instead report such input through their
+//! return value and never panic, whatever their arguments:
+//!
+//! * `pow_mod`, `legendre_symbol`, `tonelli_shanks` and `square_roots`,
+//!   which return `Result<_, TonelliError>`
+//! * their `u64` versions `pow_mod_checked`, `legendre_symbol_checked`,
+//!   `tonelli_shanks_checked` and `square_roots_checked`
+//! * `mod_inverse`, `crt` and `crt_check`
+//! * `sqrt_gaussian` and `principal_sqrt_p3mod4`
+//! * `PrimeField::from_parts`
+//!
+//! `tests/no_panic.rs` checks this on a grid of adversarial inputs.
+//!
+//! # Overflow
+//!
+//! No function relies on wrapping arithmetic: modular products of `u64`
+//! values are computed in `u128`, so moduli up to `u64::MAX` are supported
+//! throughout. The test profile enables `overflow-checks`, and
+//! `tests/no_overflow.rs` runs the core functions on primes just below
+//! 2^64. To check an optimized build as well, run
+//! `RUSTFLAGS="-C overflow-checks=on" cargo test --release`.
+
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+pub mod compat;
+mod field;
+mod kernel;
+mod narrow;
+mod wide;
+
+pub use field::{
+    CachedPrimeField, LEGENDRE_CACHE_SIZE, PrimeField, SqrtIterExt, sqrt_across_fields,
+};
+pub use narrow::tonelli_shanks_u32;
+pub use wide::{legendre_symbol_u128, pow_mod_u128, square_roots_u128, tonelli_shanks_u128};
+
+/// The integer type of the core functions, `u64` unless `width-u128` is enabled
+#[cfg(not(feature = "width-u128"))]
+pub type Int = u64;
+
+/// The integer type of the core functions, `u64` unless `width-u128` is enabled
+#[cfg(feature = "width-u128")]
+pub type Int = u128;
+
+#[cfg(not(feature = "width-u128"))]
+use self::{
+    legendre_symbol_u64 as legendre_symbol_int, pow_mod_u64 as pow_mod_int,
+    square_roots_u64 as square_roots_int, tonelli_shanks_u64 as tonelli_shanks_int,
+};
+#[cfg(feature = "width-u128")]
+use wide::{
+    legendre_symbol_u128 as legendre_symbol_int, pow_mod_u128 as pow_mod_int,
+    square_roots_u128 as square_roots_int, tonelli_shanks_u128 as tonelli_shanks_int,
+};
+
+use std::fmt;
+use std::hint::black_box;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Errors reported by the checked variants of the square-root functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TonelliError {
+    /// The modulus was zero
+    ModulusZero,
+    /// The modulus was even and not equal to 2, so it cannot be an odd prime
+    ModulusEven,
+    /// The cached parameters of a `PrimeField` do not match its modulus
+    InvalidField,
+}
+
+impl fmt::Display for TonelliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TonelliError::ModulusZero => write!(f, "modulus must be non-zero"),
+            TonelliError::ModulusEven => write!(f, "modulus must be 2 or an odd prime"),
+            TonelliError::InvalidField => write!(f, "field parameters do not match the modulus"),
+        }
+    }
+}
+
+impl std::error::Error for TonelliError {}
+
+/// Tally of the field operations performed while computing a square root
+///
+/// Squarings are counted separately from general multiplications since they
+/// are often cheaper in optimized field implementations. No algorithm in this
+/// crate currently performs field inversions, so `invs` stays zero for now.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpCount {
+    /// Number of general field multiplications
+    pub mults: u64,
+    /// Number of field squarings
+    pub sqrs: u64,
+    /// Number of field inversions
+    pub invs: u64,
+}
+
+/// Checks that p is usable as a modulus by the square-root functions
+fn check_modulus(p: impl Into<u128>) -> Result<(), TonelliError> {
+    let p = p.into();
+    if p == 0 {
+        Err(TonelliError::ModulusZero)
+    } else if p != 2 && p.is_multiple_of(2) {
+        Err(TonelliError::ModulusEven)
+    } else {
+        Ok(())
+    }
+}
+
+/// Computes modular multiplication: a * b mod p without overflowing
+const fn mul_mod(a: u64, b: u64, p: u64) -> u64 {
+    ((a as u128 * b as u128) % p as u128) as u64
+}
+
+/// Computes modular addition: a + b mod p without overflowing
+fn add_mod(a: u64, b: u64, p: u64) -> u64 {
+    ((a as u128 + b as u128) % p as u128) as u64
+}
+
+kernel::tonelli_kernel!(u64);
 
 /// Computes modular exponentiation: x^n mod p
 ///
@@ -10,35 +158,152 @@
 /// # Arguments
 /// * `x` - The base
 /// * `n` - The exponent
+/// * `p` - The modulus
+///
+/// # Returns
+/// * `Ok(x^n mod p)` for a non-zero modulus
+/// * `Err(TonelliError::ModulusZero)` if p is zero
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{TonelliError, pow_mod};
+///
+/// assert_eq!(pow_mod(2, 10, 1000), Ok(24));
+/// assert_eq!(pow_mod(3, 5, 7), Ok(5));
+/// assert_eq!(pow_mod(3, 5, 0), Err(TonelliError::ModulusZero));
+/// ```
+pub fn pow_mod(x: Int, n: Int, p: Int) -> Result<Int, TonelliError> {
+    if p == 0 {
+        return Err(TonelliError::ModulusZero);
+    }
+    Ok(pow_mod_int(x, n, p))
+}
+
+/// Computes modular exponentiation x^n mod p on `u64`, whatever the `Int` width
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::pow_mod_u64;
+///
+/// assert_eq!(pow_mod_u64(3, 5, 7), 5);
+/// ```
+pub fn pow_mod_u64(x: u64, n: u64, p: u64) -> u64 {
+    pow_mod_counted::<false>(x, n, p, &mut OpCount::default())
+}
+
+/// Computes the modular square: x² mod p
+///
+/// The multiplication is done in 128 bits, so this never overflows.
+///
+/// # Arguments
+/// * `x` - The number to square
 /// * `p` - The modulus (must be positive)
 ///
 /// # Returns
-/// The result of x^n mod p
+/// The result of x² mod p
 ///
 /// # Examples
 /// ```
-/// use tonelli_rs::pow_mod;
+/// use tonelli_rs::square_mod;
 ///
-/// assert_eq!(pow_mod(2, 10, 1000), 24);
-/// assert_eq!(pow_mod(3, 5, 7), 5);
+/// assert_eq!(square_mod(4, 7), 2);
+/// assert_eq!(square_mod(u64::MAX, 1000000007), 114944269);
 /// ```
-pub fn pow_mod(mut x: u64, mut n: u64, p: u64) -> u64 {
+pub fn square_mod(x: u64, p: u64) -> u64 {
     if p == 0 {
         panic!();
     }
 
-    let mut result = 1;
-    x %= p;
+    mul_mod(x % p, x % p, p)
+}
 
-    while n > 0 {
-        if n & 1 == 1 {
-            result = (result * x) % p;
-        }
-        x = (x * x) % p;
-        n >>= 1;
+/// Computes the additive inverse: -x mod p
+///
+/// # Arguments
+/// * `x` - The number to negate
+/// * `p` - The modulus (must be positive)
+///
+/// # Returns
+/// The value in [0, p) congruent to -x
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::neg_mod;
+///
+/// assert_eq!(neg_mod(3, 7), 4);
+/// assert_eq!(neg_mod(0, 7), 0);
+/// ```
+pub fn neg_mod(x: u64, p: u64) -> u64 {
+    (p - x % p) % p
+}
+
+/// Computes the multiplicative inverse: x^-1 mod p
+///
+/// Uses the extended Euclidean algorithm, so p does not need to be prime.
+///
+/// # Arguments
+/// * `x` - The number to invert
+/// * `p` - The modulus
+///
+/// # Returns
+/// * `Some(y)` where x·y ≡ 1 (mod p)
+/// * `None` if x and p are not coprime or p is zero
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::mod_inverse;
+///
+/// assert_eq!(mod_inverse(3, 7), Some(5));
+/// assert_eq!(mod_inverse(2, 4), None);
+/// ```
+pub fn mod_inverse(x: u64, p: u64) -> Option<u64> {
+    if p == 0 {
+        return None;
     }
 
-    result
+    let (mut old_r, mut r) = (x as i128 % p as i128, p as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+
+    if old_r != 1 {
+        return None;
+    }
+
+    Some(old_s.rem_euclid(p as i128) as u64)
+}
+
+/// Computes modular exponentiation, reporting a zero modulus as an error
+///
+/// The `u64` version of `pow_mod`, whatever the `Int` width. Behaves like
+/// `pow_mod_u64` but returns an error instead of panicking when the
+/// modulus is zero.
+///
+/// # Arguments
+/// * `x` - The base
+/// * `n` - The exponent
+/// * `p` - The modulus
+///
+/// # Returns
+/// * `Ok(x^n mod p)` for a non-zero modulus
+/// * `Err(TonelliError::ModulusZero)` if p is zero
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{pow_mod_checked, TonelliError};
+///
+/// assert_eq!(pow_mod_checked(3, 5, 7), Ok(5));
+/// assert_eq!(pow_mod_checked(3, 5, 0), Err(TonelliError::ModulusZero));
+/// ```
+pub fn pow_mod_checked(x: u64, n: u64, p: u64) -> Result<u64, TonelliError> {
+    if p == 0 {
+        return Err(TonelliError::ModulusZero);
+    }
+    Ok(pow_mod_u64(x, n, p))
 }
 
 /// Computes the Legendre symbol (a/p)
@@ -53,29 +318,123 @@ pub fn pow_mod(mut x: u64, mut n: u64, p: u64) -> u64 {
 /// * `p` - The prime modulus
 ///
 /// # Returns
-/// The Legendre symbol as an i32
+/// * `Ok(symbol)` with the Legendre symbol as an i32 for a valid modulus
+/// * `Err(TonelliError)` if p is zero, or even and not 2
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{TonelliError, legendre_symbol};
+///
+/// assert_eq!(legendre_symbol(2, 7), Ok(1));  // 2 is a quadratic residue mod 7
+/// assert_eq!(legendre_symbol(3, 7), Ok(-1)); // 3 is a quadratic non-residue mod 7
+/// assert_eq!(legendre_symbol(3, 8), Err(TonelliError::ModulusEven));
+/// ```
+pub fn legendre_symbol(a: Int, p: Int) -> Result<i32, TonelliError> {
+    check_modulus(p)?;
+    Ok(legendre_symbol_int(a, p))
+}
+
+/// Computes the Legendre symbol (a/p) on `u64`, whatever the `Int` width
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::legendre_symbol_u64;
+///
+/// assert_eq!(legendre_symbol_u64(3, 7), -1);
+/// ```
+pub fn legendre_symbol_u64(a: u64, p: u64) -> i32 {
+    legendre_symbol_counted::<false>(a, p, &mut OpCount::default())
+}
+
+/// Computes the Legendre symbol (a/p), reporting invalid moduli as errors
+///
+/// The `u64` version of `legendre_symbol`, whatever the `Int` width.
+/// Behaves like `legendre_symbol_u64` but returns an error instead of
+/// panicking when the modulus is zero or even.
+///
+/// # Arguments
+/// * `a` - The number to check
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Ok(symbol)` for a valid modulus
+/// * `Err(TonelliError)` if p is not a valid modulus
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{legendre_symbol_checked, TonelliError};
+///
+/// assert_eq!(legendre_symbol_checked(3, 7), Ok(-1));
+/// assert_eq!(legendre_symbol_checked(3, 0), Err(TonelliError::ModulusZero));
+/// ```
+pub fn legendre_symbol_checked(a: u64, p: u64) -> Result<i32, TonelliError> {
+    check_modulus(p)?;
+    Ok(legendre_symbol_u64(a, p))
+}
+
+/// Computes the Jacobi symbol (a/m) for an odd modulus m
+///
+/// The Jacobi symbol extends the Legendre symbol to odd composite moduli and
+/// agrees with it when m is prime. It is computed with quadratic reciprocity,
+/// so no factorization of m is needed. A value of 1 does not imply that a is
+/// a square modulo a composite m.
+///
+/// # Arguments
+/// * `a` - The numerator
+/// * `m` - The odd modulus
+///
+/// # Returns
+/// The Jacobi symbol as an i32 (0 when gcd(a, m) > 1)
 ///
 /// # Examples
 /// ```
-/// use tonelli_rs::legendre_symbol;
+/// use tonelli_rs::jacobi_symbol;
 ///
-/// assert_eq!(legendre_symbol(2, 7), 1);  // 2 is a quadratic residue mod 7
-/// assert_eq!(legendre_symbol(3, 7), -1); // 3 is a quadratic non-residue mod 7
+/// assert_eq!(jacobi_symbol(2, 7), 1);
+/// assert_eq!(jacobi_symbol(2, 15), 1); // yet 2 is not a square mod 15
+/// assert_eq!(jacobi_symbol(3, 15), 0);
 /// ```
-pub fn legendre_symbol(a: u64, p: u64) -> i32 {
-    let a_mod_p = a % p;
-    if a_mod_p == 0 {
-        return 0;
+pub fn jacobi_symbol(a: u64, m: u64) -> i32 {
+    if m.is_multiple_of(2) {
+        panic!();
     }
 
-    let result = pow_mod(a_mod_p, (p - 1) / 2, p);
-    if result == 1 {
-        1
-    } else if result == p - 1 {
-        -1
-    } else {
-        0
+    let (mut a, mut m) = (a % m, m);
+    let mut result = 1;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if m % 8 == 3 || m % 8 == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut m);
+        if a % 4 == 3 && m % 4 == 3 {
+            result = -result;
+        }
+        a %= m;
     }
+
+    if m == 1 { result } else { 0 }
+}
+
+/// Computes the Jacobi symbol (n/m) for every odd modulus in a slice
+///
+/// # Arguments
+/// * `n` - The numerator shared by every symbol
+/// * `moduli` - The odd moduli
+///
+/// # Returns
+/// `jacobi_symbol(n, m)` for each m, in the order of `moduli`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::jacobi_symbols;
+///
+/// assert_eq!(jacobi_symbols(2, &[7, 15, 5, 9]), vec![1, 1, -1, 1]);
+/// ```
+pub fn jacobi_symbols(n: u64, moduli: &[u64]) -> Vec<i32> {
+    moduli.iter().map(|&m| jacobi_symbol(n, m)).collect()
 }
 
 /// Finds the first quadratic non-residue modulo p
@@ -96,42 +455,208 @@ pub fn legendre_symbol(a: u64, p: u64) -> i32 {
 /// assert_eq!(find_quadratic_non_residue(7), 3);
 /// ```
 pub fn find_quadratic_non_residue(p: u64) -> u64 {
-    for z in 2..p {
-        if legendre_symbol(z, p) == -1 {
-            return z;
-        }
-    }
-    unreachable!();
+    find_quadratic_non_residue_counted::<false>(p, &mut OpCount::default())
+        .expect("no quadratic non-residue, p is not an odd prime")
 }
 
 /// Computes a square root of n modulo p using the Tonelli-Shanks algorithm
 ///
 /// This function finds r such that r² ≡ n (mod p) if n is a quadratic residue.
+/// The canonical root, the smaller of r and p - r, is returned.
 ///
 /// # Arguments
 /// * `n` - The number to find the square root of
 /// * `p` - The prime modulus
 ///
 /// # Returns
-/// * `Some(r)` if n is a quadratic residue modulo p, where r² ≡ n (mod p)
-/// * `None` if n is not a quadratic residue modulo p
+/// * `Ok(Some(r))` if n is a quadratic residue modulo p, where r² ≡ n (mod p) and r ≤ p - r
+/// * `Ok(Some(0))` if n ≡ 0 (mod p), which includes n = p and every other multiple of p
+/// * `Ok(None)` if n is not a quadratic residue modulo p
+/// * `Err(TonelliError)` if p is zero, or even and not 2
 ///
 /// # Examples
 /// ```
-/// use tonelli_rs::tonelli_shanks;
+/// use tonelli_rs::{TonelliError, tonelli_shanks};
 ///
-/// // 2 is a quadratic residue modulo 7: 4² ≡ 2 (mod 7)
-/// assert_eq!(tonelli_shanks(2, 7), Some(4));
+/// // 2 is a quadratic residue modulo 7: 3² ≡ 4² ≡ 2 (mod 7)
+/// assert_eq!(tonelli_shanks(2, 7), Ok(Some(3)));
 ///
 /// // 3 is not a quadratic residue modulo 7
-/// assert_eq!(tonelli_shanks(3, 7), None);
+/// assert_eq!(tonelli_shanks(3, 7), Ok(None));
+///
+/// // Multiples of p reduce to 0, whose only root is 0
+/// assert_eq!(tonelli_shanks(7, 7), Ok(Some(0)));
+/// assert_eq!(tonelli_shanks(14, 7), Ok(Some(0)));
+///
+/// assert_eq!(tonelli_shanks(2, 8), Err(TonelliError::ModulusEven));
+/// ```
+pub fn tonelli_shanks(n: Int, p: Int) -> Result<Option<Int>, TonelliError> {
+    check_modulus(p)?;
+    Ok(tonelli_shanks_int(n, p))
+}
+
+/// Computes a square root of n modulo p on `u64`, whatever the `Int` width
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::tonelli_shanks_u64;
+///
+/// assert_eq!(tonelli_shanks_u64(2, 7), Some(3));
+/// ```
+pub fn tonelli_shanks_u64(n: u64, p: u64) -> Option<u64> {
+    tonelli_shanks_traced::<false>(n, p, &mut Trace::new())
+}
+
+/// Details recorded while running Tonelli-Shanks
+#[derive(Debug)]
+struct Trace {
+    /// Field operations performed by Tonelli-Shanks itself, excluding any
+    /// fallback, only tallied when the functions run with `COUNT` set
+    ops: OpCount,
+    /// Whether Tonelli-Shanks failed on a residue and the fallback ran
+    fallback: bool,
+    /// The successive values of m in the main loop, the first `ms_len` entries
+    ///
+    /// m starts at s ≤ 127 and strictly decreases, so 128 entries always
+    /// fit. A fixed array keeps the trace usable from `const fn`s.
+    ms: [u8; 128],
+    /// Number of recorded values of m
+    ms_len: usize,
+    /// Candidates the non-residue search examined, 0 if it did not run or
+    /// `COUNT` is not set
+    nr_candidates: u32,
+}
+
+impl Trace {
+    /// Creates an empty trace
+    const fn new() -> Self {
+        Trace {
+            ops: OpCount {
+                mults: 0,
+                sqrs: 0,
+                invs: 0,
+            },
+            fallback: false,
+            ms: [0; 128],
+            ms_len: 0,
+            nr_candidates: 0,
+        }
+    }
+
+    /// Records the current value of m in the main loop
+    const fn push_m(&mut self, m: u32) {
+        self.ms[self.ms_len] = m as u8;
+        self.ms_len += 1;
+    }
+}
+
+/// Runs Tonelli-Shanks, recording what happened into `trace`
+///
+/// For a genuine prime the main loop always terminates with `t == 1`. If it
+/// instead runs out of order, or no non-residue exists, p cannot be prime.
+/// Cipolla's algorithm would fail on such a p as well, because it relies on
+/// GF(p²) being a field, so the root is recovered from the factorization of
+/// p with `modular_sqrt_any` instead.
+fn tonelli_shanks_traced<const COUNT: bool>(n: u64, p: u64, trace: &mut Trace) -> Option<u64> {
+    tonelli_shanks_raw::<COUNT>(n, p, trace).map(|r| canonical_root(r, p))
+}
+
+/// Runs Tonelli-Shanks without normalizing the root it finds
+fn tonelli_shanks_raw<const COUNT: bool>(n: u64, p: u64, trace: &mut Trace) -> Option<u64> {
+    let root = tonelli_shanks_core::<COUNT>(n, p, trace);
+    if trace.fallback {
+        return modular_sqrt_any(n % p, p);
+    }
+    root
+}
+
+/// Computes a square root of n modulo p, flagging whether the fallback ran
+///
+/// `tonelli_shanks` falls back to `modular_sqrt_any`, which factors p, if
+/// its main loop fails after n has passed Euler's criterion. That cannot
+/// happen for a prime modulus, so a raised flag indicates a composite p.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The result of `tonelli_shanks_u64(n, p)` and `true` if the fallback was used
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::tonelli_shanks_flagged;
+///
+/// assert_eq!(tonelli_shanks_flagged(2, 17), (Some(6), false));
+/// ```
+pub fn tonelli_shanks_flagged(n: u64, p: u64) -> (Option<u64>, bool) {
+    let mut trace = Trace::new();
+    let root = tonelli_shanks_traced::<false>(n, p, &mut trace);
+    (root, trace.fallback)
+}
+
+/// Computes the termination certificate of the Tonelli-Shanks main loop
+///
+/// The loop keeps the invariant that t has order 2^i for some i < m, and
+/// each iteration replaces m by that i. The recorded values of m, starting
+/// from s where p - 1 = 2^s · q, must therefore strictly decrease, which
+/// bounds the loop by s iterations. A verifier can check exactly that.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(ms)` with the successive values of m, empty if the loop did not run
+///   (n ≡ 0, p = 2 or the p ≡ 3 (mod 4) fast path)
+/// * `None` if `tonelli_shanks_u64(n, p)` has no root or the main loop failed
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::tonelli_shanks_certificate;
+///
+/// // 16 = 2^4, so m starts at 4
+/// assert_eq!(tonelli_shanks_certificate(2, 17), Some(vec![4, 3]));
+/// assert_eq!(tonelli_shanks_certificate(2, 7), Some(vec![]));
+/// assert_eq!(tonelli_shanks_certificate(3, 17), None);
+/// ```
+pub fn tonelli_shanks_certificate(n: u64, p: u64) -> Option<Vec<u32>> {
+    let mut trace = Trace::new();
+    tonelli_shanks_traced::<false>(n, p, &mut trace)?;
+    if trace.fallback {
+        return None;
+    }
+    Some(trace.ms[..trace.ms_len].iter().map(|&m| m as u32).collect())
+}
+
+/// Computes a square root of n modulo p using Cipolla's algorithm
+///
+/// Finds a such that a² - n is a quadratic non-residue, then computes
+/// (a + ω)^((p+1)/2) in GF(p²) = GF(p)[ω] with ω² = a² - n. The result
+/// lies in GF(p) and is a square root of n. The root is verified before
+/// being returned, and the canonical (smaller) root is chosen.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(r)` if n is a quadratic residue modulo p, where r² ≡ n (mod p)
+/// * `None` if n is not a quadratic residue modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::cipolla;
+///
+/// assert_eq!(cipolla(2, 17), Some(6));
+/// assert_eq!(cipolla(3, 17), None);
 /// ```
-pub fn tonelli_shanks(n: u64, p: u64) -> Option<u64> {
+pub fn cipolla(n: u64, p: u64) -> Option<u64> {
     if p == 2 {
         return Some(n % 2);
     }
 
-    if p % 2 == 0 {
+    if p.is_multiple_of(2) {
         panic!();
     }
 
@@ -140,73 +665,2885 @@ pub fn tonelli_shanks(n: u64, p: u64) -> Option<u64> {
         return Some(0);
     }
 
-    if legendre_symbol(n_mod_p, p) != 1 {
+    if legendre_symbol_u64(n_mod_p, p) != 1 {
         return None;
     }
 
-    if p % 4 == 3 {
-        let r = pow_mod(n_mod_p, (p + 1) / 4, p);
-        return Some(r);
-    }
-
-    let mut s = 0;
-    let mut q = p - 1;
-    while q % 2 == 0 {
-        q /= 2;
-        s += 1;
-    }
-
-    let z = find_quadratic_non_residue(p);
-    let mut c = pow_mod(z, q, p);
-    let mut r = pow_mod(n_mod_p, (q + 1) / 2, p);
-    let mut t = pow_mod(n_mod_p, q, p);
-    let mut m = s;
+    cipolla_counted::<false>(n_mod_p, p, &mut OpCount::default()).map(|r| canonical_root(r, p))
+}
 
-    while t != 1 {
-        let mut tt = t;
-        let mut i = 0;
+/// Runs Cipolla's algorithm for a reduced n, tallying the field operations into `ops` when `COUNT` is set
+fn cipolla_counted<const COUNT: bool>(n: u64, p: u64, ops: &mut OpCount) -> Option<u64> {
+    let mut a = 0;
+    let w = loop {
+        if a == p {
+            return None;
+        }
+        let w = add_mod(mul_mod(a, a, p), p - n, p);
+        if legendre_symbol_counted::<COUNT>(w, p, ops) == -1 {
+            break w;
+        }
+        a += 1;
+    };
 
-        while tt != 1 {
-            tt = (tt * tt) % p;
-            i += 1;
-            if i == m {
-                return None;
+    let mut result = (1, 0);
+    let mut base = (a, 1);
+    let mut e = p.div_ceil(2);
+    while e > 0 {
+        if e & 1 == 1 {
+            result = fp2_mul(result, base, w, p);
+            if COUNT {
+                ops.mults += 5;
             }
         }
-
-        let b = pow_mod(c, 1 << (m - i - 1), p);
-        let b2 = (b * b) % p;
-        r = (r * b) % p;
-        t = (t * b2) % p;
-        c = b2;
-        m = i;
+        base = fp2_mul(base, base, w, p);
+        if COUNT {
+            ops.mults += 5;
+        }
+        e >>= 1;
     }
 
-    Some(r)
+    let r = result.0;
+    if result.1 == 0 && mul_mod(r, r, p) == n {
+        Some(r)
+    } else {
+        None
+    }
 }
 
-/// Computes both square roots of n modulo p
+/// Number of multiples of p that `sqrt_kunerth` tries before falling back
+const KUNERTH_SEARCH_LIMIT: u64 = 64;
+
+/// Computes a square root of n modulo p using Kunerth's method where it applies
 ///
-/// If n is a quadratic residue modulo p, this function returns both square roots.
+/// Kunerth's method reads the root off an integer identity instead of
+/// working in the multiplicative group: if n + k·p = s² for some k ≥ 0, then
+/// s² ≡ n (mod p) and s is a root. The search walks n mod p, n mod p + p,
+/// ... up to `KUNERTH_SEARCH_LIMIT` multiples and takes one integer square
+/// root per step, with no modular exponentiation and no non-residue.
+///
+/// The method applies when n mod p is a perfect square, or lies just below
+/// a square by a small multiple of p, which covers the small residues a
+/// perfect square produces and any n whose root is below about √(64·p).
+/// For every other n, including all non-residues, it falls back to
+/// `modular_sqrt`, so the result is the same either way.
 ///
 /// # Arguments
-/// * `n` - The number to find the square roots of
+/// * `n` - The number to find the square root of
 /// * `p` - The prime modulus
 ///
 /// # Returns
-/// * `Some((r1, r2))` if n is a quadratic residue, where r1 and r2 are the two square roots
-/// * `None` if n is not a quadratic residue
+/// * `Some(r)` if n is a quadratic residue modulo p, where r² ≡ n (mod p) and r ≤ p - r
+/// * `None` if n is not a quadratic residue modulo p
 ///
 /// # Examples
 /// ```
-/// use tonelli_rs::square_roots;
+/// use tonelli_rs::{modular_sqrt, sqrt_kunerth};
 ///
-/// let roots = square_roots(2, 7);
-/// assert_eq!(roots, Some((3, 4))); // 3² ≡ 4² ≡ 2 (mod 7)
+/// // 25 = 2 + 23 is a square, so 5 is read off directly
+/// assert_eq!(sqrt_kunerth(2, 23), Some(5));
+/// assert_eq!(sqrt_kunerth(2, 17), modular_sqrt(2, 17));
+/// assert_eq!(sqrt_kunerth(3, 17), None);
 /// ```
-pub fn square_roots(n: u64, p: u64) -> Option<(u64, u64)> {
-    tonelli_shanks(n, p).map(|r| {
-        let r2 = p - r;
-        if r < r2 { (r, r2) } else { (r2, r) }
-    })
+pub fn sqrt_kunerth(n: u64, p: u64) -> Option<u64> {
+    if p > 2 && !p.is_multiple_of(2) {
+        let wide_p = p as u128;
+        let mut lifted = (n % p) as u128;
+        for _ in 0..KUNERTH_SEARCH_LIMIT {
+            let s = lifted.isqrt();
+            if s * s == lifted {
+                return Some(canonical_root((s % wide_p) as u64, p));
+            }
+            lifted += wide_p;
+        }
+    }
+
+    modular_sqrt(n, p)
+}
+
+/// Computes a square root of n modulo p, reporting invalid moduli as errors
+///
+/// The `u64` version of `tonelli_shanks`, whatever the `Int` width.
+/// Behaves like `tonelli_shanks_u64` but returns an error instead of
+/// panicking when the modulus is zero or even. Odd composite moduli are not detected,
+/// but never cause a panic either.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Ok(Some(r))` if n is a quadratic residue modulo p, where r² ≡ n (mod p)
+/// * `Ok(None)` if n is not a quadratic residue modulo p
+/// * `Err(TonelliError)` if p is not a valid modulus
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{tonelli_shanks_checked, TonelliError};
+///
+/// assert_eq!(tonelli_shanks_checked(2, 7), Ok(Some(3)));
+/// assert_eq!(tonelli_shanks_checked(3, 7), Ok(None));
+/// assert_eq!(tonelli_shanks_checked(2, 8), Err(TonelliError::ModulusEven));
+/// ```
+pub fn tonelli_shanks_checked(n: u64, p: u64) -> Result<Option<u64>, TonelliError> {
+    check_modulus(p)?;
+    Ok(tonelli_shanks_u64(n, p))
+}
+
+/// Computes both square roots of n modulo p
+///
+/// If n is a quadratic residue modulo p, this function returns both square roots.
+/// The roots are negatives of each other, so for n ≢ 0 (mod p) they always
+/// satisfy r1 + r2 = p. The exception is n ≡ 0 (mod p), whose only square
+/// root is 0, returned as `(0, 0)`.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Ok(Some((r1, r2)))` if n is a quadratic residue, where r1 ≤ r2 are the two square roots
+/// * `Ok(Some((0, 0)))` if n ≡ 0 (mod p), as 0 is the only root
+/// * `Ok(None)` if n is not a quadratic residue
+/// * `Err(TonelliError)` if p is zero, or even and not 2
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{TonelliError, square_roots};
+///
+/// let roots = square_roots(2, 7);
+/// assert_eq!(roots, Ok(Some((3, 4)))); // 3² ≡ 4² ≡ 2 (mod 7)
+/// assert_eq!(square_roots(0, 7), Ok(Some((0, 0))));
+/// assert_eq!(square_roots(7, 7), Ok(Some((0, 0))));
+/// assert_eq!(square_roots(2, 0), Err(TonelliError::ModulusZero));
+/// ```
+pub fn square_roots(n: Int, p: Int) -> Result<Option<(Int, Int)>, TonelliError> {
+    check_modulus(p)?;
+    Ok(square_roots_int(n, p))
+}
+
+/// Computes both square roots of n modulo p on `u64`, whatever the `Int` width
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::square_roots_u64;
+///
+/// assert_eq!(square_roots_u64(2, 7), Some((3, 4)));
+/// ```
+pub fn square_roots_u64(n: u64, p: u64) -> Option<(u64, u64)> {
+    tonelli_shanks_u64(n, p).map(|r| {
+        if r == 0 {
+            return (0, 0);
+        }
+
+        let r2 = p - r;
+        debug_assert_eq!(r.checked_add(r2), Some(p));
+        if r < r2 { (r, r2) } else { (r2, r) }
+    })
+}
+
+/// Computes both square roots of n modulo p, reporting invalid moduli as errors
+///
+/// The `u64` version of `square_roots`, whatever the `Int` width. Behaves
+/// like `square_roots_u64` but returns an error instead of panicking when
+/// the modulus is zero or even.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Ok(Some((r1, r2)))` if n is a quadratic residue, where r1 and r2 are the two square roots
+/// * `Ok(None)` if n is not a quadratic residue
+/// * `Err(TonelliError)` if p is not a valid modulus
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{square_roots_checked, TonelliError};
+///
+/// assert_eq!(square_roots_checked(2, 7), Ok(Some((3, 4))));
+/// assert_eq!(square_roots_checked(3, 7), Ok(None));
+/// assert_eq!(square_roots_checked(2, 0), Err(TonelliError::ModulusZero));
+/// ```
+pub fn square_roots_checked(n: u64, p: u64) -> Result<Option<(u64, u64)>, TonelliError> {
+    check_modulus(p)?;
+    Ok(square_roots_u64(n, p))
+}
+
+/// Finds the fixed points of squaring modulo p
+///
+/// Returns every x in [0, p) with x² ≡ x (mod p), i.e. the values that are
+/// one of their own square roots. For a prime p these are exactly 0 and 1,
+/// while composite moduli also have non-trivial idempotents.
+///
+/// # Arguments
+/// * `p` - The modulus (must be positive)
+///
+/// # Returns
+/// The fixed points in ascending order
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_fixed_points;
+///
+/// assert_eq!(sqrt_fixed_points(7), vec![0, 1]);
+/// assert_eq!(sqrt_fixed_points(6), vec![0, 1, 3, 4]);
+/// ```
+pub fn sqrt_fixed_points(p: u64) -> Vec<u64> {
+    if p == 0 {
+        panic!();
+    }
+
+    (0..p).filter(|&x| mul_mod(x, x, p) == x % p).collect()
+}
+
+/// A precomputed set of the quadratic residues modulo a small prime p
+///
+/// Stores one bit per element of [0, p), so building the set takes O(p) time
+/// and about p/8 bytes of memory, after which each membership query is O(1).
+/// This is faster than calling `legendre_symbol` repeatedly against the same
+/// small prime.
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::ResidueSet;
+///
+/// let set = ResidueSet::new(7);
+/// assert!(set.contains(2));  // 3² ≡ 2 (mod 7)
+/// assert!(!set.contains(3)); // 3 is a quadratic non-residue mod 7
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResidueSet {
+    bits: Vec<u64>,
+    p: u64,
+}
+
+impl ResidueSet {
+    /// Builds the residue set for the prime modulus p
+    ///
+    /// # Arguments
+    /// * `p` - The prime modulus
+    ///
+    /// # Returns
+    /// A `ResidueSet` marking every nonzero square modulo p
+    pub fn new(p: u64) -> Self {
+        if p == 0 {
+            panic!();
+        }
+
+        let mut bits = vec![0u64; p.div_ceil(64) as usize];
+        for x in 1..p {
+            let sq = mul_mod(x, x, p);
+            if sq != 0 {
+                bits[(sq / 64) as usize] |= 1 << (sq % 64);
+            }
+        }
+
+        ResidueSet { bits, p }
+    }
+
+    /// Checks whether n is a nonzero quadratic residue modulo p
+    ///
+    /// # Arguments
+    /// * `n` - The number to check
+    ///
+    /// # Returns
+    /// `true` exactly when `legendre_symbol_u64(n, p) == 1`
+    pub fn contains(&self, n: u64) -> bool {
+        let n_mod_p = n % self.p;
+        self.bits[(n_mod_p / 64) as usize] & (1 << (n_mod_p % 64)) != 0
+    }
+}
+
+/// Checks whether candidate is a square root of n modulo p
+///
+/// Both `candidate` and `n` are reduced modulo p first, so unreduced
+/// representations such as `r + p` are accepted as long as their square
+/// matches n.
+///
+/// # Arguments
+/// * `n` - The number the candidate should be a square root of
+/// * `p` - The modulus
+/// * `candidate` - The proposed square root, reduced or not
+///
+/// # Returns
+/// `true` if candidate² ≡ n (mod p)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::roots_match;
+///
+/// assert!(roots_match(2, 7, 4));
+/// assert!(roots_match(2, 7, 11)); // 11 ≡ 4 (mod 7)
+/// assert!(!roots_match(2, 7, 5));
+/// ```
+pub fn roots_match(n: u64, p: u64, candidate: u64) -> bool {
+    let c = candidate % p;
+    mul_mod(c, c, p) == n % p
+}
+
+/// Multiplies two elements of GF(p²) represented as pairs x + y·ω with ω² = w
+fn fp2_mul(x: (u64, u64), y: (u64, u64), w: u64, p: u64) -> (u64, u64) {
+    let re =
+        (mul_mod(x.0, y.0, p) as u128 + mul_mod(mul_mod(x.1, y.1, p), w, p) as u128) % p as u128;
+    let im = (mul_mod(x.0, y.1, p) as u128 + mul_mod(x.1, y.0, p) as u128) % p as u128;
+    (re as u64, im as u64)
+}
+
+/// Computes a square root of a + b·i in GF(p²) = GF(p)[i] where i² = -1
+///
+/// The extension is only a field when -1 is a quadratic non-residue, i.e.
+/// when p ≡ 3 (mod 4). Every element of GF(p) has a square root there, and
+/// a + b·i has one exactly when its norm a² + b² is a square in GF(p).
+///
+/// # Arguments
+/// * `a` - The real part
+/// * `b` - The imaginary part
+/// * `p` - The prime modulus with p ≡ 3 (mod 4)
+///
+/// # Returns
+/// * `Some((c, d))` where (c + d·i)² ≡ a + b·i (mod p), choosing the root
+///   with the smaller (c, d) of the two roots ±(c + d·i)
+/// * `None` if a + b·i is not a square, or if p ≢ 3 (mod 4)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_gaussian;
+///
+/// // (1 + 2i)² = -3 + 4i ≡ 4 + 4i (mod 7)
+/// let (c, d) = sqrt_gaussian(4, 4, 7).unwrap();
+/// assert_eq!(((c * c + 7 * 7 - d * d) % 7, (2 * c * d) % 7), (4, 4));
+/// ```
+pub fn sqrt_gaussian(a: u64, b: u64, p: u64) -> Option<(u64, u64)> {
+    if p % 4 != 3 {
+        return None;
+    }
+
+    let a = a % p;
+    let b = b % p;
+
+    if b == 0 {
+        return match tonelli_shanks_u64(a, p) {
+            Some(c) => Some((c, 0)),
+            None => tonelli_shanks_u64(p - a, p).map(|d| (0, d)),
+        };
+    }
+
+    let norm = add_mod(mul_mod(a, a, p), mul_mod(b, b, p), p);
+    let t = tonelli_shanks_u64(norm, p)?;
+    let inv_two = p.div_ceil(2);
+
+    for t in [t, (p - t) % p] {
+        let half = mul_mod(add_mod(a, t, p), inv_two, p);
+        if let Some(c) = tonelli_shanks_u64(half, p)
+            && c != 0
+        {
+            let inv_two_c = pow_mod_u64(mul_mod(2, c, p), p - 2, p);
+            let d = mul_mod(b, inv_two_c, p);
+            if fp2_mul((c, d), (c, d), p - 1, p) == (a, b) {
+                return Some((c, d).min((p - c, (p - d) % p)));
+            }
+        }
+    }
+
+    None
+}
+
+/// Computes a square root of n modulo p along with the field operations used
+///
+/// Runs the same algorithm as `tonelli_shanks`, including the Legendre symbol
+/// check and the non-residue search, and counts every multiplication and
+/// squaring performed. This is useful for cost models that budget field
+/// operations rather than wall-clock time.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The result of `tonelli_shanks_u64(n, p)` together with an `OpCount`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{modular_sqrt_opcount, OpCount};
+///
+/// let (root, ops) = modular_sqrt_opcount(2, 7);
+/// assert_eq!(root, Some(3));
+/// assert_eq!(ops, OpCount { mults: 3, sqrs: 4, invs: 0 });
+/// ```
+pub fn modular_sqrt_opcount(n: u64, p: u64) -> (Option<u64>, OpCount) {
+    let mut trace = Trace::new();
+    let root = tonelli_shanks_traced::<true>(n, p, &mut trace);
+    (root, trace.ops)
+}
+
+/// Computes the Legendre symbol of a product of factors modulo p
+///
+/// The Legendre symbol is multiplicative, so the symbol of a·b·… is the
+/// product of the individual symbols. This avoids forming the (possibly
+/// overflowing) product itself.
+///
+/// # Arguments
+/// * `factors` - The numbers whose product should be checked
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The Legendre symbol of the product as an i32 (1 for an empty slice)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::legendre_symbol_product;
+///
+/// assert_eq!(legendre_symbol_product(&[3, 5], 7), 1);  // 3·5 ≡ 1 (mod 7)
+/// assert_eq!(legendre_symbol_product(&[2, 3], 7), -1); // 2·3 ≡ 6 (mod 7)
+/// ```
+pub fn legendre_symbol_product(factors: &[u64], p: u64) -> i32 {
+    let mut result = 1;
+    for &a in factors {
+        result *= legendre_symbol_u64(a, p);
+        if result == 0 {
+            return 0;
+        }
+    }
+    result
+}
+
+/// Explains why n has no square root modulo p
+///
+/// Uses Euler's criterion: n is a quadratic non-residue exactly when
+/// n^((p-1)/2) ≡ -1 (mod p), so the computed power serves as a witness.
+///
+/// # Arguments
+/// * `n` - The number without a square root
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(explanation)` if n is a quadratic non-residue modulo p
+/// * `None` if n has a square root modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::explain_no_root;
+///
+/// assert_eq!(
+///     explain_no_root(3, 7).unwrap(),
+///     "3 is a quadratic non-residue mod 7 because 3^3 ≡ 6 ≡ -1 (mod 7)"
+/// );
+/// assert_eq!(explain_no_root(2, 7), None);
+/// ```
+pub fn explain_no_root(n: u64, p: u64) -> Option<String> {
+    if legendre_symbol_u64(n, p) != -1 {
+        return None;
+    }
+
+    let e = (p - 1) / 2;
+    let witness = pow_mod_u64(n, e, p);
+    Some(format!(
+        "{} is a quadratic non-residue mod {} because {}^{} ≡ {} ≡ -1 (mod {})",
+        n, p, n, e, witness, p
+    ))
+}
+
+/// Number of primes processed together by `legendre_symbols_simd`
+#[cfg(feature = "simd")]
+const SIMD_LANES: usize = 8;
+
+/// Computes the Legendre symbol (a/p) for many primes at once using SIMD
+///
+/// Primes below 2^32 are processed in groups of 8 lanes of `u64`, running
+/// the square-and-multiply exponentiation of Euler's criterion in all lanes
+/// at once. Since every product of two reduced values fits in 64 bits there
+/// is no need for wider intermediates. Primes of 2^32 or more fall back to
+/// the scalar `legendre_symbol`, as do the leftover primes that do not fill
+/// a whole group.
+///
+/// Requires the `simd` feature and a nightly compiler.
+///
+/// # Arguments
+/// * `a` - The number to check
+/// * `primes` - The odd prime moduli
+///
+/// # Returns
+/// The Legendre symbol (a/p) for each prime, in the same order as `primes`
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "simd")]
+/// # {
+/// use tonelli_rs::legendre_symbols_simd;
+///
+/// assert_eq!(legendre_symbols_simd(2, &[3, 5, 7, 17]), vec![-1, -1, 1, 1]);
+/// # }
+/// ```
+#[cfg(feature = "simd")]
+pub fn legendre_symbols_simd(a: u64, primes: &[u64]) -> Vec<i32> {
+    let mut symbols = vec![0; primes.len()];
+    let mut lanes = [0u64; SIMD_LANES];
+    let mut lane_idx = [0usize; SIMD_LANES];
+    let mut filled = 0;
+
+    for (i, &p) in primes.iter().enumerate() {
+        if p < 1 << 32 && !a.is_multiple_of(p) {
+            lanes[filled] = p;
+            lane_idx[filled] = i;
+            filled += 1;
+            if filled == SIMD_LANES {
+                let lane_symbols = legendre_symbol_lanes(a, lanes);
+                for lane in 0..SIMD_LANES {
+                    symbols[lane_idx[lane]] = lane_symbols[lane];
+                }
+                filled = 0;
+            }
+        } else {
+            symbols[i] = legendre_symbol_u64(a, p);
+        }
+    }
+
+    for lane in 0..filled {
+        symbols[lane_idx[lane]] = legendre_symbol_u64(a, lanes[lane]);
+    }
+
+    symbols
+}
+
+/// Computes the Legendre symbol (a/p) for a full group of primes below 2^32
+#[cfg(feature = "simd")]
+fn legendre_symbol_lanes(a: u64, primes: [u64; SIMD_LANES]) -> [i32; SIMD_LANES] {
+    use std::simd::Select;
+    use std::simd::Simd;
+    use std::simd::cmp::SimdPartialEq;
+
+    let p = Simd::from_array(primes);
+    let zero = Simd::splat(0);
+    let one = Simd::splat(1);
+
+    let mut x = Simd::splat(a) % p;
+    let mut e = (p - one) >> one;
+    let mut result = one;
+
+    while e.simd_ne(zero).any() {
+        let bit = (e & one).simd_eq(one);
+        result = bit.select((result * x) % p, result);
+        x = (x * x) % p;
+        e >>= one;
+    }
+
+    let result = result.to_array();
+    let mut symbols = [0; SIMD_LANES];
+    for lane in 0..SIMD_LANES {
+        symbols[lane] = if result[lane] == 1 {
+            1
+        } else if result[lane] == primes[lane] - 1 {
+            -1
+        } else {
+            0
+        };
+    }
+    symbols
+}
+
+/// Computes the compression bit of an elliptic curve y-coordinate
+///
+/// A point (x, y) can be stored as x plus one bit selecting between the two
+/// square roots ±y of the curve equation's right-hand side. Since p is odd,
+/// y and p - y always have opposite parity, so the parity of y is that bit.
+///
+/// # Arguments
+/// * `y` - The y-coordinate
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// `true` if y mod p is odd
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::compress_y;
+///
+/// assert!(compress_y(3, 7));
+/// assert!(!compress_y(4, 7));
+/// ```
+pub fn compress_y(y: u64, p: u64) -> bool {
+    (y % p) & 1 == 1
+}
+
+/// Recovers an elliptic curve y-coordinate from its compression bit
+///
+/// Computes the square roots of the curve equation's right-hand side and
+/// returns the one whose parity matches `y_bit`, undoing `compress_y`.
+///
+/// # Arguments
+/// * `x_rhs` - The value of the curve equation's right-hand side at x
+/// * `p` - The prime modulus
+/// * `y_bit` - The compression bit produced by `compress_y`
+///
+/// # Returns
+/// * `Some(y)` where y² ≡ x_rhs (mod p) and `compress_y(y, p) == y_bit`
+/// * `None` if x_rhs is not a quadratic residue, or if no root has the requested parity
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::decompress_y;
+///
+/// assert_eq!(decompress_y(2, 7, true), Some(3));
+/// assert_eq!(decompress_y(2, 7, false), Some(4));
+/// assert_eq!(decompress_y(3, 7, true), None);
+/// ```
+pub fn decompress_y(x_rhs: u64, p: u64, y_bit: bool) -> Option<u64> {
+    let (r1, r2) = square_roots_u64(x_rhs, p)?;
+    [r1, r2].into_iter().find(|&r| compress_y(r, p) == y_bit)
+}
+
+/// Computes all square roots of n modulo 2
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+///
+/// # Returns
+/// Every x in [0, 2) with x² ≡ n (mod 2), in ascending order
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_mod_2;
+///
+/// assert_eq!(sqrt_mod_2(3), vec![1]);
+/// ```
+pub fn sqrt_mod_2(n: u64) -> Vec<u64> {
+    vec![n % 2]
+}
+
+/// Computes all square roots of n modulo 4
+///
+/// Squares modulo 4 are only 0 and 1, each with two roots.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+///
+/// # Returns
+/// Every x in [0, 4) with x² ≡ n (mod 4), in ascending order
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_mod_4;
+///
+/// assert_eq!(sqrt_mod_4(1), vec![1, 3]);
+/// assert_eq!(sqrt_mod_4(3), vec![]);
+/// ```
+pub fn sqrt_mod_4(n: u64) -> Vec<u64> {
+    match n % 4 {
+        0 => vec![0, 2],
+        1 => vec![1, 3],
+        _ => vec![],
+    }
+}
+
+/// Computes all square roots of n modulo 8
+///
+/// Squares modulo 8 are only 0, 1 and 4. Notably every odd number squares
+/// to 1, so x² ≡ 1 (mod 8) has four solutions.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+///
+/// # Returns
+/// Every x in [0, 8) with x² ≡ n (mod 8), in ascending order
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_mod_8;
+///
+/// assert_eq!(sqrt_mod_8(1), vec![1, 3, 5, 7]);
+/// assert_eq!(sqrt_mod_8(4), vec![2, 6]);
+/// assert_eq!(sqrt_mod_8(5), vec![]);
+/// ```
+pub fn sqrt_mod_8(n: u64) -> Vec<u64> {
+    match n % 8 {
+        0 => vec![0, 4],
+        1 => vec![1, 3, 5, 7],
+        4 => vec![2, 6],
+        _ => vec![],
+    }
+}
+
+/// Lists the first quadratic residues modulo p together with a square root
+///
+/// Scans 1, 2, 3, … upward and collects the values that are nonzero
+/// quadratic residues, reusing a single `PrimeField` for all the roots.
+/// This is handy for generating example data and test vectors.
+///
+/// # Arguments
+/// * `p` - The prime modulus
+/// * `n` - How many residues to return
+///
+/// # Returns
+/// Up to n pairs (residue, root) with root² ≡ residue (mod p), fewer if p has
+/// less than n nonzero residues
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::first_n_residues_with_roots;
+///
+/// assert_eq!(first_n_residues_with_roots(7, 2), vec![(1, 1), (2, 3)]);
+/// ```
+pub fn first_n_residues_with_roots(p: u64, n: usize) -> Vec<(u64, u64)> {
+    let field = PrimeField::new(p);
+    (1..p)
+        .filter_map(|x| field.sqrt(x).map(|r| (x, r)))
+        .take(n)
+        .collect()
+}
+
+/// Tuned default for the 2-adic valuation above which `modular_sqrt` uses Cipolla
+///
+/// Tonelli-Shanks needs O(s²) multiplications where p - 1 = 2^s · q, while
+/// Cipolla's cost only depends on the size of p, so Cipolla wins once s is
+/// large enough. `modular_sqrt` uses this value until `calibrate_threshold`
+/// has been called.
+pub const DEFAULT_CIPOLLA_THRESHOLD: u32 = 20;
+
+/// The threshold measured by `calibrate_threshold`, once it has run
+static CALIBRATED_THRESHOLD: OnceLock<u32> = OnceLock::new();
+
+/// Primes just above 2^31 with p - 1 = 2^s · q for s = 2, 4, …, 30
+const CALIBRATION_PRIMES: [(u32, u64); 15] = [
+    (2, 2147483693),
+    (4, 2147483857),
+    (6, 2147483713),
+    (8, 2147484929),
+    (10, 2147494913),
+    (12, 2147577857),
+    (14, 2147565569),
+    (16, 2148728833),
+    (18, 2148794369),
+    (20, 2154823681),
+    (22, 2151677953),
+    (24, 2533359617),
+    (26, 2483027969),
+    (28, 3489660929),
+    (30, 3221225473),
+];
+
+/// Number of square roots timed per prime and algorithm during calibration
+const CALIBRATION_ROUNDS: u32 = 64;
+
+/// Picks the Tonelli-Shanks/Cipolla crossover for the current hardware
+///
+/// On the first call this times both algorithms on a fixed set of primes of
+/// increasing 2-adic valuation s, which takes a few milliseconds. The result
+/// is cached, so later calls return immediately, and `modular_sqrt` uses it
+/// from then on instead of `DEFAULT_CIPOLLA_THRESHOLD`. Cipolla is preferred for
+/// primes whose s is strictly greater than the returned threshold. If
+/// Tonelli-Shanks wins for every tested prime the threshold is 32.
+///
+/// # Returns
+/// The calibrated threshold, between 2 and 32
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::calibrate_threshold;
+///
+/// let threshold = calibrate_threshold();
+/// assert!((2..=32).contains(&threshold));
+/// ```
+pub fn calibrate_threshold() -> u32 {
+    *CALIBRATED_THRESHOLD.get_or_init(|| {
+        let mut threshold = 2;
+        for (s, p) in CALIBRATION_PRIMES {
+            // z² has the largest possible 2-power order, the worst case for Tonelli-Shanks
+            let z = find_quadratic_non_residue(p);
+            let n = mul_mod(z, z, p);
+
+            let start = Instant::now();
+            for _ in 0..CALIBRATION_ROUNDS {
+                black_box(tonelli_shanks_u64(black_box(n), p));
+            }
+            let tonelli = start.elapsed();
+
+            let start = Instant::now();
+            for _ in 0..CALIBRATION_ROUNDS {
+                black_box(cipolla(black_box(n), p));
+            }
+            let cipolla = start.elapsed();
+
+            if cipolla < tonelli {
+                return threshold;
+            }
+            threshold = s;
+        }
+        32
+    })
+}
+
+/// Computes a square root of n modulo p, picking the faster algorithm for p
+///
+/// Primes with p ≡ 3 (mod 4) use the direct exponentiation. Otherwise,
+/// Tonelli-Shanks is used unless the 2-adic valuation s of p - 1 exceeds
+/// the threshold, in which case Cipolla's algorithm is used. The threshold
+/// is `DEFAULT_CIPOLLA_THRESHOLD` until `calibrate_threshold()` has been
+/// called, so no timing runs unless asked for. Both algorithms return the
+/// canonical root, so the result is the same either way.
+///
+/// The output is deterministic: the root Tonelli-Shanks arrives at depends
+/// on the non-residue it starts from, but every choice leads to r or p - r,
+/// and reducing to the smaller one makes the result a function of n and p
+/// alone. Callers that hash or sign the root can rely on this.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(r)` if n is a quadratic residue modulo p, where r² ≡ n (mod p) and r ≤ p - r
+/// * `None` if n is not a quadratic residue modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::modular_sqrt;
+///
+/// assert_eq!(modular_sqrt(2, 7), Some(3));
+/// assert_eq!(modular_sqrt(3, 7), None);
+/// assert_eq!(modular_sqrt(2, 17), Some(6)); // never 11
+/// ```
+pub fn modular_sqrt(n: u64, p: u64) -> Option<u64> {
+    let threshold = CALIBRATED_THRESHOLD
+        .get()
+        .copied()
+        .unwrap_or(DEFAULT_CIPOLLA_THRESHOLD);
+    if p % 4 == 1 && (p - 1).trailing_zeros() > threshold {
+        cipolla(n, p)
+    } else {
+        tonelli_shanks_u64(n, p)
+    }
+}
+
+/// Computes every square root of n modulo p along with an independent check
+///
+/// Each root is paired with a freshly recomputed `square_mod(r, p) == n % p`.
+/// In correct operation every flag is `true`; exposing it lets high-assurance
+/// callers assert verification without redoing the arithmetic themselves.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The distinct roots in ascending order with their verification flags,
+/// or an empty vector if n is a quadratic non-residue
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::roots_verified;
+///
+/// assert_eq!(roots_verified(2, 7), vec![(3, true), (4, true)]);
+/// assert_eq!(roots_verified(0, 7), vec![(0, true)]);
+/// assert_eq!(roots_verified(3, 7), vec![]);
+/// ```
+pub fn roots_verified(n: u64, p: u64) -> Vec<(u64, bool)> {
+    let mut roots = match square_roots_u64(n, p) {
+        Some((r1, r2)) => vec![r1, r2],
+        None => return Vec::new(),
+    };
+    roots.dedup();
+
+    roots
+        .into_iter()
+        .map(|r| (r, square_mod(r, p) == n % p))
+        .collect()
+}
+
+/// Computes a square root of n modulo p together with its inverses
+///
+/// Elliptic curve formulas often need y, -y and y^-1 at the same time.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some((y, neg_mod(y, p), mod_inverse(y, p)))` for the canonical root y
+/// * `None` if n is a quadratic non-residue, or if y = 0 has no inverse
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_with_inverses;
+///
+/// assert_eq!(sqrt_with_inverses(2, 7), Some((3, 4, 5)));
+/// assert_eq!(sqrt_with_inverses(0, 7), None);
+/// ```
+pub fn sqrt_with_inverses(n: u64, p: u64) -> Option<(u64, u64, u64)> {
+    let y = tonelli_shanks_u64(n, p)?;
+    let inverse = mod_inverse(y, p)?;
+    Some((y, neg_mod(y, p), inverse))
+}
+
+/// Finds the first quadratic non-residue modulo p at or after k
+///
+/// Generalizes `find_quadratic_non_residue` to an arbitrary starting point.
+/// The search starts at k mod p and wraps around from p - 1 to 0.
+///
+/// # Arguments
+/// * `k` - Where to start searching
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(n)` for the first n in k, k + 1, … (mod p) that is a non-residue
+/// * `None` if p has no quadratic non-residues (p = 2)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::next_non_residue;
+///
+/// assert_eq!(next_non_residue(4, 7), Some(5));
+/// assert_eq!(next_non_residue(7, 7), Some(3)); // 7 ≡ 0, then 1 and 2 are residues
+/// ```
+pub fn next_non_residue(k: u64, p: u64) -> Option<u64> {
+    let start = k % p;
+    (start..p)
+        .chain(0..start)
+        .find(|&n| legendre_symbol_u64(n, p) == -1)
+}
+
+/// Timings of the `u64` and `u128` square-root code paths for one prime
+#[cfg(feature = "bench-api")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchReport {
+    /// Average nanoseconds per `tonelli_shanks_u64` call
+    pub u64_ns_per_op: f64,
+    /// Average nanoseconds per `tonelli_shanks_u128` call
+    pub u128_ns_per_op: f64,
+}
+
+/// Times the `u64` and `u128` Tonelli-Shanks implementations for the prime p
+///
+/// Both paths compute square roots of the same inputs 1, 2, …, iters, so the
+/// mix of residues and non-residues is identical. The numbers are wall-clock
+/// measurements: they include timer overhead, depend on CPU frequency
+/// scaling and whatever else the machine is doing, and are only meaningful
+/// in an optimized build. Use enough iterations for the total to be well
+/// above the timer resolution, and compare ratios rather than absolute
+/// values.
+///
+/// Requires the `bench-api` feature.
+///
+/// # Arguments
+/// * `p` - The prime modulus
+/// * `iters` - The number of square roots to time per path (at least 1 is used)
+///
+/// # Returns
+/// A `BenchReport` with the average time per call of each path
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "bench-api")]
+/// # {
+/// use tonelli_rs::bench_report;
+///
+/// let report = bench_report(998244353, 100);
+/// assert!(report.u64_ns_per_op > 0.0 && report.u128_ns_per_op > 0.0);
+/// # }
+/// ```
+#[cfg(feature = "bench-api")]
+pub fn bench_report(p: u64, iters: u32) -> BenchReport {
+    let iters = iters.max(1);
+
+    let start = Instant::now();
+    for n in 1..=iters as u64 {
+        black_box(tonelli_shanks_u64(black_box(n), p));
+    }
+    let u64_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    for n in 1..=iters as u128 {
+        black_box(tonelli_shanks_u128(black_box(n), p as u128));
+    }
+    let u128_elapsed = start.elapsed();
+
+    // A zero reading only means the run was below the timer resolution
+    let ns_per_op = |elapsed: std::time::Duration| elapsed.as_nanos().max(1) as f64 / iters as f64;
+    BenchReport {
+        u64_ns_per_op: ns_per_op(u64_elapsed),
+        u128_ns_per_op: ns_per_op(u128_elapsed),
+    }
+}
+
+/// Writes the square roots of n modulo p into a caller-provided buffer
+///
+/// Never allocates, which makes it suitable for FFI and embedded callers
+/// that own their buffers. Entries of `out` past the returned count are
+/// left untouched.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `p` - The prime modulus
+/// * `out` - The buffer receiving the roots in ascending order
+///
+/// # Returns
+/// The number of distinct roots written: 0 for a non-residue, 1 when the
+/// only root is its own negative (n ≡ 0, or p = 2), and 2 otherwise
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::square_roots_into;
+///
+/// let mut out = [0; 2];
+/// assert_eq!(square_roots_into(2, 7, &mut out), 2);
+/// assert_eq!(out, [3, 4]);
+/// ```
+pub fn square_roots_into(n: u64, p: u64, out: &mut [u64; 2]) -> usize {
+    match square_roots_u64(n, p) {
+        Some((r1, r2)) if r1 == r2 => {
+            out[0] = r1;
+            1
+        }
+        Some((r1, r2)) => {
+            *out = [r1, r2];
+            2
+        }
+        None => 0,
+    }
+}
+
+/// Computes the fraction of integers in [lo, hi) that are quadratic residues mod p
+///
+/// Multiples of p are counted in the interval length but never as residues,
+/// so over a full period of p consecutive integers the density is exactly
+/// ((p - 1) / 2) / p, which approaches 1/2. Shorter intervals fluctuate
+/// around that value.
+///
+/// # Arguments
+/// * `p` - The prime modulus
+/// * `lo` - The start of the interval (inclusive)
+/// * `hi` - The end of the interval (exclusive)
+///
+/// # Returns
+/// The residue density, or 0.0 for an empty interval
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::residue_density;
+///
+/// assert_eq!(residue_density(7, 0, 7), 3.0 / 7.0);
+/// assert_eq!(residue_density(7, 1, 3), 1.0); // 1 and 2 are both residues
+/// ```
+pub fn residue_density(p: u64, lo: u64, hi: u64) -> f64 {
+    if hi <= lo {
+        return 0.0;
+    }
+
+    let residues = (lo..hi).filter(|&n| legendre_symbol_u64(n, p) == 1).count();
+    residues as f64 / (hi - lo) as f64
+}
+
+/// Generates `#[test]` functions checking the square-root invariants for primes
+///
+/// Each `name: prime` entry expands to a test that compares the crate
+/// against brute force over every n in [0, p): residues are detected
+/// exactly, `tonelli_shanks` returns the smallest root, and `square_roots`
+/// returns two roots summing to p. Brute force takes O(p) time and memory,
+/// so keep the primes to a few million at most.
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_tests;
+///
+/// sqrt_tests! {
+///     sqrt_invariants_7: 7,
+///     sqrt_invariants_13: 13,
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! sqrt_tests {
+    ($($name:ident: $p:expr),+ $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                $crate::check_sqrt_invariants($p);
+            }
+        )+
+    };
+}
+
+/// Checks the square-root invariants for every n modulo p against brute force
+///
+/// Used by the tests generated with `sqrt_tests!`. Panics on the first
+/// invariant that does not hold.
+#[doc(hidden)]
+pub fn check_sqrt_invariants(p: u64) {
+    let mut smallest_root = vec![None; p as usize];
+    for x in (0..p).rev() {
+        smallest_root[mul_mod(x, x, p) as usize] = Some(x);
+    }
+
+    for n in 0..p {
+        let expected = smallest_root[n as usize];
+        let symbol = legendre_symbol_u64(n, p);
+        assert_eq!(
+            expected.is_some(),
+            symbol != -1,
+            "residue detection for n = {n}, p = {p}"
+        );
+        assert_eq!(
+            tonelli_shanks_u64(n, p),
+            expected,
+            "root of n = {n}, p = {p}"
+        );
+
+        match (square_roots_u64(n, p), expected) {
+            (Some((0, 0)), Some(0)) => {}
+            (Some((r1, r2)), Some(r)) => {
+                assert_eq!(r1, r, "smaller root of n = {n}, p = {p}");
+                assert_eq!(mul_mod(r2, r2, p), n, "larger root of n = {n}, p = {p}");
+                assert_eq!(r1 + r2, p, "root sum of n = {n}, p = {p}");
+            }
+            (None, None) => {}
+            (roots, _) => panic!("square_roots({n}, {p}) returned {roots:?}"),
+        }
+    }
+}
+
+/// Computes how many times a square root can be taken repeatedly starting from n
+///
+/// Returns the largest k such that n has a 2^k-th root in GF(p). With
+/// p - 1 = 2^s · q and t = n^q, the order of t is 2^j for some j ≤ s, and a
+/// 2^k-th root exists exactly when k ≤ s - j. Equivalently, writing n = g^e
+/// for a primitive root g, the depth is the 2-adic valuation of e. When
+/// t = 1 (n has odd order, for example n = 1) roots of every 2-power order
+/// exist and the depth is unbounded.
+///
+/// # Arguments
+/// * `n` - The number to take repeated square roots of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The depth, 0 for a non-residue, or `u32::MAX` if it is unbounded
+/// (including n ≡ 0 and p = 2)
+///
+/// # Panics
+/// Panics if p is 0 or even and not 2, or if t does not reach 1 within s
+/// squarings, which proves p composite. Other composite moduli give a
+/// meaningless depth.
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_tower_depth;
+///
+/// assert_eq!(sqrt_tower_depth(3, 17), 0); // non-residue
+/// assert_eq!(sqrt_tower_depth(2, 17), 1); // 2 ≡ 3^14 and v2(14) = 1
+/// assert_eq!(sqrt_tower_depth(16, 17), 3); // 16 ≡ 6^8
+/// assert_eq!(sqrt_tower_depth(1, 17), u32::MAX);
+/// ```
+pub fn sqrt_tower_depth(n: u64, p: u64) -> u32 {
+    if p == 2 {
+        return u32::MAX;
+    }
+
+    if p.is_multiple_of(2) {
+        panic!();
+    }
+
+    let n_mod_p = n % p;
+    if n_mod_p == 0 {
+        return u32::MAX;
+    }
+
+    let (s, q) = split_two_adic(p);
+    let mut t = pow_mod_u64(n_mod_p, q, p);
+    if t == 1 {
+        return u32::MAX;
+    }
+
+    let mut j = 0;
+    while t != 1 {
+        if j == s {
+            panic!("{} is not prime: the order of t is not a power of 2", p);
+        }
+        t = mul_mod(t, t, p);
+        j += 1;
+    }
+    s - j
+}
+
+/// Iterates over every nonzero quadratic residue modulo p with its canonical root
+///
+/// Squares x for x in 1..=p/2 instead of testing each n for residuosity, so
+/// no Legendre symbols are computed and the whole iteration is O(p). Each
+/// x ≤ p/2 is the smaller root of its square and distinct x give distinct
+/// squares, so every residue appears exactly once. The residues come out
+/// in the order of their roots, not sorted.
+///
+/// # Arguments
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// An iterator over (residue, root) pairs with root² ≡ residue (mod p)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::residues_iter;
+///
+/// let pairs: Vec<_> = residues_iter(7).collect();
+/// assert_eq!(pairs, vec![(1, 1), (4, 2), (2, 3)]);
+/// ```
+pub fn residues_iter(p: u64) -> impl Iterator<Item = (u64, u64)> {
+    (1..=p / 2).map(move |x| (mul_mod(x, x, p), x))
+}
+
+/// Lists the square roots of every n in 1..p in the same quadratic class as a reference
+///
+/// Only the residue class has roots, so this is all of `residues_iter(p)`
+/// when the reference is a quadratic residue and empty otherwise. The
+/// non-residues, and a reference ≡ 0 whose class in 1..p is empty, give no
+/// pairs.
+///
+/// # Arguments
+/// * `reference` - A value whose Legendre symbol selects the class
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The (n, root) pairs of the class, in the order of `residues_iter`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::roots_of_class;
+///
+/// assert_eq!(roots_of_class(2, 7), vec![(1, 1), (4, 2), (2, 3)]);
+/// assert_eq!(roots_of_class(3, 7), vec![]);
+/// ```
+pub fn roots_of_class(reference: u64, p: u64) -> Vec<(u64, u64)> {
+    if legendre_symbol_u64(reference, p) == 1 {
+        residues_iter(p).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Computes the right-hand side x³ + ax + b (mod p) of a short Weierstrass curve
+///
+/// # Arguments
+/// * `x` - The x-coordinate
+/// * `a` - The curve coefficient a
+/// * `b` - The curve coefficient b
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The value of x³ + ax + b reduced modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::curve_rhs;
+///
+/// assert_eq!(curve_rhs(3, 2, 3, 97), 36); // 27 + 6 + 3
+/// ```
+pub fn curve_rhs(x: u64, a: u64, b: u64, p: u64) -> u64 {
+    let x = x % p;
+    let x3 = mul_mod(mul_mod(x, x, p), x, p);
+    let ax = mul_mod(a % p, x, p);
+    ((x3 as u128 + ax as u128 + (b % p) as u128) % p as u128) as u64
+}
+
+/// Checks whether (x, y) lies on the curve y² = x³ + ax + b (mod p)
+///
+/// # Arguments
+/// * `x` - The x-coordinate
+/// * `y` - The y-coordinate
+/// * `a` - The curve coefficient a
+/// * `b` - The curve coefficient b
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// `true` if y² ≡ x³ + ax + b (mod p)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::is_on_curve;
+///
+/// assert!(is_on_curve(3, 6, 2, 3, 97));
+/// assert!(!is_on_curve(3, 7, 2, 3, 97));
+/// ```
+pub fn is_on_curve(x: u64, y: u64, a: u64, b: u64, p: u64) -> bool {
+    square_mod(y, p) == curve_rhs(x, a, b, p)
+}
+
+/// Recovers the y-coordinates of the points on y² = x³ + ax + b with the given x
+///
+/// # Arguments
+/// * `x` - The x-coordinate
+/// * `a` - The curve coefficient a
+/// * `b` - The curve coefficient b
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some((y1, y2))` with y1 ≤ y2 the two candidate y-coordinates
+///   (equal to `(0, 0)` when the right-hand side is 0)
+/// * `None` if no point on the curve has this x-coordinate
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::decompress_point;
+///
+/// assert_eq!(decompress_point(3, 2, 3, 97), Some((6, 91)));
+/// ```
+pub fn decompress_point(x: u64, a: u64, b: u64, p: u64) -> Option<(u64, u64)> {
+    square_roots_u64(curve_rhs(x, a, b, p), p)
+}
+
+/// Recovers the y-coordinates for many x-coordinates on y² = x³ + ax + b
+///
+/// Builds one `PrimeField` for p and reuses its cached non-residue and
+/// roots of unity for every x, instead of redoing that setup per point.
+///
+/// # Arguments
+/// * `xs` - The x-coordinates
+/// * `a` - The curve coefficient a
+/// * `b` - The curve coefficient b
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// One entry per x, in order, equal to `decompress_point(x, a, b, p)`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::decompress_points;
+///
+/// assert_eq!(
+///     decompress_points(&[3, 5, 10], 2, 3, 97),
+///     vec![Some((6, 91)), None, Some((21, 76))]
+/// );
+/// ```
+pub fn decompress_points(xs: &[u64], a: u64, b: u64, p: u64) -> Vec<Option<(u64, u64)>> {
+    let field = PrimeField::new(p);
+    xs.iter()
+        .map(|&x| {
+            field
+                .sqrt(curve_rhs(x, a, b, p))
+                .map(|r| if r == 0 { (0, 0) } else { (r, p - r) })
+        })
+        .collect()
+}
+
+/// Finds the first point on y² = x³ + ax + b at or after x, trying x, x + 1, ...
+///
+/// This is the try-and-increment construction used by some hash-to-curve
+/// methods: x is incremented modulo p until x³ + ax + b is a quadratic
+/// residue. A single `PrimeField` is shared by all the attempts.
+///
+/// # Arguments
+/// * `x` - The starting x-coordinate
+/// * `a` - The curve coefficient a
+/// * `b` - The curve coefficient b
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// `(x', y1, y2)` where x' is the first x-coordinate with points on the
+/// curve and y1 ≤ y2 are its y-coordinates, as in `decompress_point`
+///
+/// # Panics
+/// Panics if no x-coordinate modulo p has a point on the curve
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::decompress_or_increment;
+///
+/// // x = 5, ..., 9 are not on the curve
+/// assert_eq!(decompress_or_increment(5, 2, 3, 97), (10, 21, 76));
+/// ```
+pub fn decompress_or_increment(x: u64, a: u64, b: u64, p: u64) -> (u64, u64, u64) {
+    let field = PrimeField::new(p);
+    let mut x = x % p;
+
+    for _ in 0..p {
+        if let Some(r) = field.sqrt(curve_rhs(x, a, b, p)) {
+            return if r == 0 { (x, 0, 0) } else { (x, r, p - r) };
+        }
+        x = if x == p - 1 { 0 } else { x + 1 };
+    }
+
+    panic!("no point on the curve modulo {}", p);
+}
+
+/// Combines congruences x ≡ a (mod m) into a single one using the Chinese remainder theorem
+///
+/// # Arguments
+/// * `congruences` - Pairs (a, m) with pairwise coprime, positive moduli m
+///
+/// # Returns
+/// * `Some(x)` with x ≡ a (mod m) for every pair and 0 ≤ x < the product of the moduli
+/// * `None` if a modulus is zero, the moduli are not pairwise coprime or
+///   their product overflows `u64`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::crt;
+///
+/// assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some(23));
+/// assert_eq!(crt(&[(1, 4), (1, 6)]), None);
+/// ```
+pub fn crt(congruences: &[(u64, u64)]) -> Option<u64> {
+    let mut x = 0u64;
+    let mut modulus = 1u64;
+
+    for &(a, m) in congruences {
+        if m == 0 {
+            return None;
+        }
+
+        let inverse = mod_inverse(modulus % m, m)?;
+        let diff = (((a % m) as u128 + (m - x % m) as u128) % m as u128) as u64;
+        let k = mul_mod(diff, inverse, m);
+        let combined = modulus.checked_mul(m)?;
+        x = ((x as u128 + modulus as u128 * k as u128) % combined as u128) as u64;
+        modulus = combined;
+    }
+
+    Some(x % modulus)
+}
+
+/// Computes all square roots of n modulo the product of the primes up to a bound
+///
+/// Finds the roots modulo each prime p ≤ bound and combines every choice of
+/// per-prime root with the Chinese remainder theorem, giving up to 2^k roots
+/// for k odd primes. The primorial has to fit in a `u64`, which limits the
+/// bound to primes up to 47.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `bound` - The largest prime to include, at most 52
+///
+/// # Returns
+/// * `Some(roots)` with every root modulo the primorial in ascending order
+/// * `None` if n is a quadratic non-residue modulo any of the primes
+///
+/// # Panics
+/// If `bound` is greater than 52, since the primorial would overflow
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_mod_primorial;
+///
+/// // modulo 2 · 3 · 5 = 30
+/// assert_eq!(sqrt_mod_primorial(4, 5), Some(vec![2, 8, 22, 28]));
+/// assert_eq!(sqrt_mod_primorial(2, 5), None);
+/// ```
+pub fn sqrt_mod_primorial(n: u64, bound: u64) -> Option<Vec<u64>> {
+    assert!(
+        bound <= 52,
+        "the primorial of primes up to {bound} overflows u64"
+    );
+
+    let primes = (2..=bound).filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0));
+
+    let mut roots = combine_roots(primes.map(|p| (square_roots_prime(n, p), p)))?;
+    roots.sort_unstable();
+    (!roots.is_empty()).then_some(roots)
+}
+
+/// Lists the distinct square roots of n modulo the prime p
+fn square_roots_prime(n: u64, p: u64) -> Vec<u64> {
+    match square_roots_u64(n, p) {
+        Some((r1, r2)) if r1 != r2 => vec![r1, r2],
+        Some((r, _)) => vec![r],
+        None => Vec::new(),
+    }
+}
+
+/// Combines roots modulo pairwise coprime moduli into all roots modulo their product
+///
+/// Takes one (roots, modulus) entry per modulus and returns every CRT
+/// combination, unsorted, so a single empty list empties the result.
+/// Returns `None` if the moduli are not pairwise coprime or their product
+/// overflows, as `crt` does.
+fn combine_roots(per_modulus: impl IntoIterator<Item = (Vec<u64>, u64)>) -> Option<Vec<u64>> {
+    let mut roots = vec![(0, 1)];
+    for (residues, m) in per_modulus {
+        roots = roots
+            .iter()
+            .flat_map(|&(x, modulus)| {
+                residues
+                    .iter()
+                    .map(move |&r| Some((crt(&[(x, modulus), (r, m)])?, modulus.checked_mul(m)?)))
+            })
+            .collect::<Option<_>>()?;
+    }
+
+    Some(roots.into_iter().map(|(x, _)| x).collect())
+}
+
+/// Computes a square root of n modulo p, warning when p is costly for Tonelli-Shanks
+///
+/// The Tonelli-Shanks main loop needs up to O(s²) multiplications where
+/// p - 1 = 2^s · q. When s is above `DEFAULT_CIPOLLA_THRESHOLD` a warning is
+/// returned alongside the root, suggesting Cipolla's algorithm (or
+/// `modular_sqrt`, which picks it automatically) instead.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The result of `tonelli_shanks_u64(n, p)` and an optional warning message
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::modular_sqrt_warn;
+///
+/// assert_eq!(modular_sqrt_warn(2, 7), (Some(3), None));
+///
+/// let (_, warning) = modular_sqrt_warn(2, 3221225473); // 3 · 2^30 + 1
+/// assert_eq!(warning.unwrap(), "warning: v2(p-1) = 30 is large; consider Cipolla");
+/// ```
+pub fn modular_sqrt_warn(n: u64, p: u64) -> (Option<u64>, Option<String>) {
+    let root = tonelli_shanks_u64(n, p);
+    let s = (p - 1).trailing_zeros();
+    let warning = (p > 2 && s > DEFAULT_CIPOLLA_THRESHOLD)
+        .then(|| format!("warning: v2(p-1) = {} is large; consider Cipolla", s));
+    (root, warning)
+}
+
+/// Computes a discrete logarithm modulo p with the Pohlig-Hellman algorithm
+///
+/// Finds x with base^x ≡ target (mod p), given the factorization of any
+/// multiple N of the order of `base`, such as p - 1. The exact order is
+/// first read off the factorization, then the problem is split into one
+/// subproblem per prime power q^e dividing it, and the pieces are
+/// recombined with `crt`. Each subproblem halves its exponent recursively
+/// down to single base-q digits with only q candidates each, the same
+/// method `modular_sqrt_fast_s` uses for q = 2. This is fast whenever
+/// p - 1 is smooth, that is when every q is small.
+///
+/// # Arguments
+/// * `base` - The base of the logarithm
+/// * `target` - The number whose logarithm is wanted
+/// * `p` - The prime modulus
+/// * `order_factors` - The factorization of N as (q, e) pairs with q prime
+///
+/// # Returns
+/// * `Some(x)` with the smallest x such that base^x ≡ target (mod p)
+/// * `None` if target is not a power of base
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{discrete_log_ph, pow_mod_u64};
+///
+/// // 96 = 2^5 · 3 and 5 is a primitive root modulo 97
+/// let x = discrete_log_ph(5, 42, 97, &[(2, 5), (3, 1)]).unwrap();
+/// assert_eq!(pow_mod_u64(5, x, 97), 42);
+///
+/// // 2 only generates {1, 2, 4} modulo 7
+/// assert_eq!(discrete_log_ph(2, 3, 7, &[(2, 1), (3, 1)]), None);
+/// ```
+pub fn discrete_log_ph(
+    base: u64,
+    target: u64,
+    p: u64,
+    order_factors: &[(u64, u32)],
+) -> Option<u64> {
+    let mut order = order_factors
+        .iter()
+        .try_fold(1u64, |n, &(q, e)| n.checked_mul(q.checked_pow(e)?))?;
+    // base must be invertible to have an order at all
+    mod_inverse(base % p, p)?;
+    let target = target % p;
+
+    // Shrink N to the exact order of base, so every gamma below has order q
+    let mut factors = order_factors.to_vec();
+    for (q, e) in factors.iter_mut() {
+        while *e > 0 && pow_mod_u64(base, order / *q, p) == 1 {
+            order /= *q;
+            *e -= 1;
+        }
+    }
+
+    let mut congruences = Vec::with_capacity(factors.len());
+    for &(q, e) in factors.iter().filter(|&&(_, e)| e > 0) {
+        // c = base^(order / q^e) has order q^e, and h = c^x for x ≡ log(target) mod q^e
+        let q_e = q.pow(e);
+        let c = pow_mod_u64(base, order / q_e, p);
+        let h = pow_mod_u64(target, order / q_e, p);
+
+        // inverse_powers[j] = c^(-q^j) for j < e, with c^(-1) = c^(q^e - 1)
+        let mut inverse_powers = vec![pow_mod_u64(c, q_e - 1, p)];
+        for j in 1..e as usize {
+            inverse_powers.push(pow_mod_u64(inverse_powers[j - 1], q, p));
+        }
+
+        let mut ops = OpCount::default();
+        let x = discrete_log_prime_power::<false>(h, q, e, e, &inverse_powers, p, &mut ops)?;
+        congruences.push((x, q_e));
+    }
+
+    let x = crt(&congruences)?;
+    (pow_mod_u64(base, x, p) == target).then_some(x)
+}
+
+/// Computes x < q^k with h = γ^x, where γ = c^(q^(s-k)) generates the subgroup of order q^k
+///
+/// `inverse_powers[j]` holds c^(-q^j) for c of order q^s, so γ^(-q^j) is
+/// entry s - k + j. Writing x = x_low + q^low · x_high, each half is found
+/// in a smaller subgroup, which takes O(k log k) multiplications instead of
+/// the O(k²) of finding one digit at a time. Returns `None` when some digit
+/// has no match, so h is not a power of γ. For q = 2 the last digit is not
+/// checked, which leaves that to the caller's final verification.
+fn discrete_log_prime_power<const COUNT: bool>(
+    h: u64,
+    q: u64,
+    k: u32,
+    s: u32,
+    inverse_powers: &[u64],
+    p: u64,
+    ops: &mut OpCount,
+) -> Option<u64> {
+    match k {
+        0 => return (h == 1).then_some(0),
+        1 if q == 2 => return Some(u64::from(h != 1)),
+        1 => {
+            // h · γ^(-d) = 1 for the digit d
+            let step = inverse_powers[s as usize - 1];
+            let mut power = h;
+            for d in 0..q {
+                if power == 1 {
+                    return Some(d);
+                }
+                power = mul_mod(power, step, p);
+                if COUNT {
+                    ops.mults += 1;
+                }
+            }
+            return None;
+        }
+        _ => {}
+    }
+
+    // h^(q^high) = (γ^(q^high))^x_low lies in the subgroup of order q^low
+    let low = k / 2;
+    let high = k - low;
+
+    let mut h_low = h;
+    for _ in 0..high {
+        h_low = if q == 2 {
+            if COUNT {
+                ops.sqrs += 1;
+            }
+            mul_mod(h_low, h_low, p)
+        } else {
+            pow_mod_counted::<COUNT>(h_low, q, p, ops)
+        };
+    }
+    let x_low = discrete_log_prime_power::<COUNT>(h_low, q, low, s, inverse_powers, p, ops)?;
+
+    // h · γ^(-x_low) = (γ^(q^low))^x_high lies in the subgroup of order q^high
+    let mut h_high = h;
+    let mut digits = x_low;
+    for j in 0..low {
+        let inverse = inverse_powers[(s - k + j) as usize];
+        let factor = match digits % q {
+            0 => None,
+            1 => Some(inverse),
+            d => Some(pow_mod_counted::<COUNT>(inverse, d, p, ops)),
+        };
+        if let Some(factor) = factor {
+            h_high = mul_mod(h_high, factor, p);
+            if COUNT {
+                ops.mults += 1;
+            }
+        }
+        digits /= q;
+    }
+    let x_high = discrete_log_prime_power::<COUNT>(h_high, q, high, s, inverse_powers, p, ops)?;
+
+    Some(x_low + q.pow(low) * x_high)
+}
+
+/// Factorizes n into prime powers by trial division
+///
+/// The output matches the `order_factors` argument of `discrete_log_ph`.
+/// Trial division runs up to √n, so this is meant for numbers of moderate
+/// size or with small factors, such as p - 1 for smooth primes.
+///
+/// # Arguments
+/// * `n` - The number to factorize
+///
+/// # Returns
+/// The (prime, exponent) pairs of n in ascending order of prime (empty for n ≤ 1)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::factorize;
+///
+/// assert_eq!(factorize(96), vec![(2, 5), (3, 1)]);
+/// assert_eq!(factorize(97), vec![(97, 1)]);
+/// assert_eq!(factorize(1), vec![]);
+/// ```
+pub fn factorize(mut n: u64) -> Vec<(u64, u32)> {
+    let mut factors = Vec::new();
+    let mut d = 2;
+    while n > 1 && d <= n / d {
+        if n.is_multiple_of(d) {
+            let mut e = 0;
+            while n.is_multiple_of(d) {
+                n /= d;
+                e += 1;
+            }
+            factors.push((d, e));
+        }
+        d += if d == 2 { 1 } else { 2 };
+    }
+
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+/// Finds the smallest primitive root modulo p
+///
+/// # Arguments
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(g)` with g the smallest generator of the multiplicative group mod p
+/// * `None` if p < 2
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::primitive_root;
+///
+/// assert_eq!(primitive_root(7), Some(3));
+/// assert_eq!(primitive_root(41), Some(6));
+/// assert_eq!(primitive_root(2), Some(1));
+/// ```
+pub fn primitive_root(p: u64) -> Option<u64> {
+    if p < 2 {
+        return None;
+    }
+    if p == 2 {
+        return Some(1);
+    }
+
+    let factors = factorize(p - 1);
+    (2..p).find(|&g| has_full_order(g, p, &factors))
+}
+
+/// Checks whether g has multiplicative order p - 1
+///
+/// Every proper divisor of p - 1 divides (p - 1) / q for some prime factor
+/// q, so g has full order exactly when none of the g^((p-1)/q) equal 1.
+///
+/// # Arguments
+/// * `g` - The candidate generator
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// `true` if g generates the multiplicative group modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::is_primitive_root;
+///
+/// assert!(is_primitive_root(3, 7));
+/// assert!(!is_primitive_root(2, 7)); // 2^3 ≡ 1 (mod 7)
+/// ```
+pub fn is_primitive_root(g: u64, p: u64) -> bool {
+    if p < 2 || g.is_multiple_of(p) {
+        return false;
+    }
+
+    has_full_order(g, p, &factorize(p - 1))
+}
+
+/// Checks g^((p-1)/q) != 1 for every prime q in the factorization of p - 1
+fn has_full_order(g: u64, p: u64, factors: &[(u64, u32)]) -> bool {
+    factors
+        .iter()
+        .all(|&(q, _)| pow_mod_u64(g, (p - 1) / q, p) != 1)
+}
+
+/// Returns a generator of the subgroup of quadratic residues modulo p
+///
+/// The nonzero squares form the subgroup of index 2, which is cyclic of
+/// order (p - 1) / 2 and generated by g² for any primitive root g. This
+/// uses the smallest primitive root.
+///
+/// # Arguments
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(g² mod p)` for primes p > 3
+/// * `None` for p < 5, where the subgroup of squares is trivial or empty
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::qr_subgroup_generator;
+///
+/// // 3 is a primitive root modulo 7, and {2, 4, 1} are its squares
+/// assert_eq!(qr_subgroup_generator(7), Some(2));
+/// assert_eq!(qr_subgroup_generator(3), None);
+/// ```
+pub fn qr_subgroup_generator(p: u64) -> Option<u64> {
+    if p < 5 {
+        return None;
+    }
+
+    primitive_root(p).map(|g| mul_mod(g, g, p))
+}
+
+/// Computes every square root of n modulo any positive modulus m
+///
+/// Factorizes m, finds the roots modulo each prime power q^e (Tonelli-Shanks
+/// for e = 1, then lifting one power of q at a time by trying the q
+/// candidates above every root) and combines them with the Chinese
+/// remainder theorem. Lifting costs q checks per root and level, so this is
+/// meant for moduli whose repeated prime factors are small.
+///
+/// The roots are always in ascending order, see `all_square_roots_ordered`
+/// for other orders.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `m` - The modulus (must be positive, need not be prime)
+///
+/// # Returns
+/// All x in 0..m with x² ≡ n (mod m) in ascending order, empty if there are none
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::all_square_roots;
+///
+/// assert_eq!(all_square_roots(2, 7), vec![3, 4]);
+/// assert_eq!(all_square_roots(0, 7), vec![0]);
+/// assert_eq!(all_square_roots(1, 8), vec![1, 3, 5, 7]);
+/// assert_eq!(all_square_roots(4, 45), vec![2, 7, 38, 43]);
+/// assert_eq!(all_square_roots(3, 7), vec![]);
+/// ```
+pub fn all_square_roots(n: u64, m: u64) -> Vec<u64> {
+    all_square_roots_ordered(n, m, RootOrder::Ascending)
+}
+
+/// Order in which functions returning several roots list them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RootOrder {
+    /// Smallest root first, the order used by `all_square_roots`
+    #[default]
+    Ascending,
+    /// Largest root first
+    Descending,
+    /// The order the roots are found in, skipping the sort
+    Unsorted,
+}
+
+/// Computes every square root of n modulo any positive modulus m in a chosen order
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `m` - The modulus (must be positive, need not be prime)
+/// * `order` - How to order the roots
+///
+/// # Returns
+/// The roots of `all_square_roots(n, m)`, ordered as requested
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{RootOrder, all_square_roots_ordered};
+///
+/// assert_eq!(all_square_roots_ordered(4, 45, RootOrder::Descending), vec![43, 38, 7, 2]);
+/// ```
+pub fn all_square_roots_ordered(n: u64, m: u64, order: RootOrder) -> Vec<u64> {
+    if m == 0 {
+        panic!();
+    }
+
+    let mut roots = combine_roots(
+        factorize(m)
+            .into_iter()
+            .map(|(q, e)| square_roots_prime_power(n, q, e)),
+    )
+    .expect("the prime powers of m are pairwise coprime");
+    match order {
+        RootOrder::Ascending => roots.sort_unstable(),
+        RootOrder::Descending => roots.sort_unstable_by(|a, b| b.cmp(a)),
+        RootOrder::Unsorted => {}
+    }
+    roots
+}
+
+/// Lists the square roots of n modulo q^e, paired with q^e
+fn square_roots_prime_power(n: u64, q: u64, e: u32) -> (Vec<u64>, u64) {
+    let mut roots = square_roots_prime(n, q);
+
+    // Every root modulo q^(k+1) reduces to a root modulo q^k
+    let mut q_k = q;
+    for _ in 1..e {
+        let q_next = q_k * q;
+        let target = n % q_next;
+        roots = roots
+            .iter()
+            .flat_map(|&x| (0..q).map(move |t| x + t * q_k))
+            .filter(|&x| mul_mod(x, x, q_next) == target)
+            .collect();
+        q_k = q_next;
+    }
+
+    (roots, q_k)
+}
+
+/// Computes the smallest square root of n modulo any positive modulus m
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `m` - The modulus (must be positive, need not be prime)
+///
+/// # Returns
+/// * `Some(x)` with the smallest x such that x² ≡ n (mod m)
+/// * `None` if n is not a square modulo m
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::modular_sqrt_any;
+///
+/// assert_eq!(modular_sqrt_any(4, 45), Some(2));
+/// assert_eq!(modular_sqrt_any(2, 7), Some(3));
+/// assert_eq!(modular_sqrt_any(2, 15), None);
+/// ```
+pub fn modular_sqrt_any(n: u64, m: u64) -> Option<u64> {
+    modular_sqrt_any_ordered(n, m, RootOrder::Ascending)
+}
+
+/// Computes the first square root of n modulo any positive modulus m in a chosen order
+///
+/// Only the first root is needed, so no sort is performed.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `m` - The modulus (must be positive, need not be prime)
+/// * `order` - Which root comes first
+///
+/// # Returns
+/// * `Some(x)` with the first root of `all_square_roots_ordered(n, m, order)`
+/// * `None` if n is not a square modulo m
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{RootOrder, modular_sqrt_any_ordered};
+///
+/// assert_eq!(modular_sqrt_any_ordered(4, 45, RootOrder::Ascending), Some(2));
+/// assert_eq!(modular_sqrt_any_ordered(4, 45, RootOrder::Descending), Some(43));
+/// ```
+pub fn modular_sqrt_any_ordered(n: u64, m: u64, order: RootOrder) -> Option<u64> {
+    let roots = all_square_roots_ordered(n, m, RootOrder::Unsorted);
+    match order {
+        RootOrder::Ascending => roots.into_iter().min(),
+        RootOrder::Descending => roots.into_iter().max(),
+        RootOrder::Unsorted => roots.first().copied(),
+    }
+}
+
+/// Computes the larger of the two square roots of n modulo p
+///
+/// For matching external conventions. sympy's `sqrt_mod` returns a root
+/// at most p // 2 for a prime modulus, which is the root given by
+/// `square_root_min` (and by `tonelli_shanks`). Raw Tonelli-Shanks or
+/// Cipolla output, as this crate returned before its results were made
+/// canonical, can be either root, so vectors recorded from such code have
+/// to be compared with `roots_match` or against both roots.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(max(r, p - r))` if n is a quadratic residue modulo p
+/// * `None` if n is not a quadratic residue modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::square_root_max;
+///
+/// assert_eq!(square_root_max(2, 7), Some(4));
+/// assert_eq!(square_root_max(0, 7), Some(0));
+/// ```
+pub fn square_root_max(n: u64, p: u64) -> Option<u64> {
+    square_roots_u64(n, p).map(|(_, r2)| r2)
+}
+
+/// Computes the smaller of the two square roots of n modulo p
+///
+/// This is the canonical root returned by `tonelli_shanks`, under a name
+/// that states the convention, see `square_root_max`.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(min(r, p - r))` if n is a quadratic residue modulo p
+/// * `None` if n is not a quadratic residue modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::square_root_min;
+///
+/// assert_eq!(square_root_min(2, 7), Some(3));
+/// ```
+pub fn square_root_min(n: u64, p: u64) -> Option<u64> {
+    square_roots_u64(n, p).map(|(r1, _)| r1)
+}
+
+/// Exponents e for which 2^e - 1 is a prime that fits in a `u64`
+const MERSENNE_EXPONENTS: [u32; 9] = [2, 3, 5, 7, 13, 17, 19, 31, 61];
+
+/// Computes a * b mod 2^exp - 1 with shifts and adds instead of a division
+fn mul_mod_mersenne(a: u64, b: u64, exp: u32) -> u64 {
+    let p = (1u64 << exp) - 1;
+    let x = a as u128 * b as u128;
+    // 2^exp ≡ 1, so the high bits fold back onto the low bits
+    let folded = (x & p as u128) + (x >> exp);
+    let folded = (folded & p as u128) as u64 + (folded >> exp) as u64;
+    if folded >= p { folded - p } else { folded }
+}
+
+/// Computes a square root of n modulo the Mersenne prime p = 2^exp - 1
+///
+/// Every such p is ≡ 3 (mod 4), so the root is n^((p+1)/4) = n^(2^(exp-2)),
+/// which takes exp - 2 squarings. Each squaring reduces modulo p by folding
+/// the high bits onto the low bits, since 2^exp ≡ 1 (mod p). Whether n is a
+/// residue is decided by checking the candidate root.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `exp` - The Mersenne exponent, one of 2, 3, 5, 7, 13, 17, 19, 31 and 61
+///
+/// # Returns
+/// * `Some(r)` with the canonical root, as returned by `tonelli_shanks_u64(n, 2^exp - 1)`
+/// * `None` if n is not a quadratic residue modulo 2^exp - 1
+///
+/// # Panics
+/// If 2^exp - 1 is not a Mersenne prime below 2^64
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::modular_sqrt_mersenne;
+///
+/// assert_eq!(modular_sqrt_mersenne(2, 3), Some(3)); // modulo 7
+/// assert_eq!(modular_sqrt_mersenne(9, 61), Some(3));
+/// assert_eq!(modular_sqrt_mersenne(3, 3), None);
+/// ```
+pub fn modular_sqrt_mersenne(n: u64, exp: u32) -> Option<u64> {
+    assert!(
+        MERSENNE_EXPONENTS.contains(&exp),
+        "2^{exp} - 1 is not a Mersenne prime below 2^64"
+    );
+
+    let p = (1u64 << exp) - 1;
+    let n = n % p;
+    let mut r = n;
+    for _ in 2..exp {
+        r = mul_mod_mersenne(r, r, exp);
+    }
+
+    (mul_mod_mersenne(r, r, exp) == n).then(|| canonical_root(r, p))
+}
+
+/// Computes all square roots of n modulo a squarefree m with known prime factors
+///
+/// Skips factorizing m: the roots modulo each given prime are combined with
+/// the Chinese remainder theorem, giving 2^k roots for k odd primes when n
+/// is coprime to m. This is for example the decryption step of the Rabin
+/// cryptosystem, where the holder of the key knows the factors.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `m` - The squarefree modulus
+/// * `prime_factors` - The distinct primes whose product is m
+///
+/// # Returns
+/// * `Some(roots)` with every root modulo m in ascending order
+/// * `None` if n is a quadratic non-residue modulo any of the primes
+/// * `None` if the factors are not pairwise coprime, for example because a
+///   prime is repeated, so m is not squarefree
+///
+/// # Panics
+/// In debug builds, if the factors do not multiply to m
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_mod_squarefree;
+///
+/// // 4 modulo 77 = 7 · 11
+/// assert_eq!(sqrt_mod_squarefree(4, 77, &[7, 11]), Some(vec![2, 9, 68, 75]));
+/// assert_eq!(sqrt_mod_squarefree(3, 77, &[7, 11]), None);
+/// ```
+pub fn sqrt_mod_squarefree(n: u64, m: u64, prime_factors: &[u64]) -> Option<Vec<u64>> {
+    debug_assert_eq!(
+        prime_factors
+            .iter()
+            .try_fold(1u64, |product, &p| product.checked_mul(p)),
+        Some(m),
+        "the prime factors do not multiply to {m}"
+    );
+
+    let coprime = prime_factors
+        .iter()
+        .enumerate()
+        .all(|(i, &p)| prime_factors[i + 1..].iter().all(|&q| gcd(p, q) == 1));
+    if !coprime {
+        return None;
+    }
+
+    let mut roots = combine_roots(prime_factors.iter().map(|&p| (square_roots_prime(n, p), p)))?;
+    roots.sort_unstable();
+    (!roots.is_empty()).then_some(roots)
+}
+
+/// Checks whether the product a · b does not fit in a `u64`
+///
+/// Before the core was made overflow-free, `pow_mod` and the Tonelli-Shanks
+/// loop multiplied residues directly in `u64`. Residues modulo p range up to
+/// p - 1, so every prime above 2^32 could produce such a product, and with
+/// it a panic in debug builds or a silently wrong root in release builds.
+/// Scanning historical inputs, for example (p - 1, p - 1) for each modulus
+/// p that was used, shows whether they could have been affected.
+///
+/// # Arguments
+/// * `a` - The first factor
+/// * `b` - The second factor
+///
+/// # Returns
+/// `true` if a · b needs more than 64 bits
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::would_overflow_u64;
+///
+/// assert!(would_overflow_u64(1 << 33, 1 << 33));
+/// assert!(!would_overflow_u64(1 << 31, 1 << 31));
+///
+/// // Any prime above 2^32 was affected
+/// let p = 4294967311;
+/// assert!(would_overflow_u64(p - 1, p - 1));
+/// ```
+pub fn would_overflow_u64(a: u64, b: u64) -> bool {
+    a.checked_mul(b).is_none()
+}
+
+/// Combines square roots modulo distinct primes into one root modulo their product
+///
+/// Each pair is a root r of some n modulo a prime p. The result x satisfies
+/// x ≡ r (mod p) for every pair, so x² ≡ n modulo the product, and is
+/// checked against every pair before being returned. This is for comparing
+/// a hand-written CRT combination of roots against the library.
+///
+/// # Arguments
+/// * `roots` - (root, prime) pairs with distinct primes
+///
+/// # Returns
+/// * `Some(x)` with x < the product of the primes
+/// * `None` if the primes are not distinct or their product overflows `u64`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::crt_check;
+///
+/// // 3 is a root of 2 mod 7, 6 is a root of 2 mod 17, so 108² ≡ 2 (mod 119)
+/// assert_eq!(crt_check(&[(3, 7), (6, 17)]), Some(108));
+/// assert_eq!(crt_check(&[(3, 7), (4, 7)]), None);
+/// ```
+pub fn crt_check(roots: &[(u64, u64)]) -> Option<u64> {
+    let x = crt(roots)?;
+    roots.iter().all(|&(r, p)| x % p == r % p).then_some(x)
+}
+
+/// Lists the edges of the functional graph of squaring modulo p
+///
+/// Every x has exactly one outgoing edge, to x², so following edges from
+/// any node ends in a cycle. Nonzero residues have two incoming edges and
+/// non-residues none, giving the graph its trees hanging off cycles. The
+/// output has p edges, one per node, so it is only practical for small p.
+///
+/// # Arguments
+/// * `p` - The modulus
+///
+/// # Returns
+/// The directed edges (x, x² mod p) for x in 0..p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_graph_edges;
+///
+/// assert_eq!(sqrt_graph_edges(5), vec![(0, 0), (1, 1), (2, 4), (3, 4), (4, 1)]);
+/// ```
+pub fn sqrt_graph_edges(p: u64) -> Vec<(u64, u64)> {
+    (0..p).map(|x| (x, square_mod(x, p))).collect()
+}
+
+/// Computes a square root of n modulo p, counting the non-residue search candidates
+///
+/// The general Tonelli-Shanks path looks for a quadratic non-residue z by
+/// testing 2, 3, 4, ... in turn, each test costing one modular
+/// exponentiation. Primes whose least non-residue is small are cheaper to
+/// set up, and this count measures exactly that.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The result of `tonelli_shanks_u64(n, p)` and the number of candidates
+/// examined, 0 if no search was needed (p ≡ 3 (mod 4), n ≡ 0 or no root)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::modular_sqrt_nr_search_cost;
+///
+/// assert_eq!(modular_sqrt_nr_search_cost(2, 7), (Some(3), 0));
+/// assert_eq!(modular_sqrt_nr_search_cost(2, 17), (Some(6), 2)); // 2 and 3
+/// ```
+pub fn modular_sqrt_nr_search_cost(n: u64, p: u64) -> (Option<u64>, u32) {
+    let mut trace = Trace::new();
+    let root = tonelli_shanks_traced::<true>(n, p, &mut trace);
+    (root, trace.nr_candidates)
+}
+
+/// Counts the square roots of n modulo any positive modulus m
+///
+/// The count is the product of the counts modulo each prime power dividing
+/// m, so no roots are combined with the Chinese remainder theorem.
+///
+/// # Arguments
+/// * `n` - The number to count the square roots of
+/// * `m` - The modulus (must be positive, need not be prime)
+///
+/// # Returns
+/// The number of x in 0..m with x² ≡ n (mod m)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::num_square_roots_composite;
+///
+/// assert_eq!(num_square_roots_composite(1, 8), 4);
+/// assert_eq!(num_square_roots_composite(4, 105), 8);
+/// assert_eq!(num_square_roots_composite(2, 15), 0);
+/// ```
+pub fn num_square_roots_composite(n: u64, m: u64) -> usize {
+    if m == 0 {
+        panic!();
+    }
+
+    count_square_roots(n, &factorize(m))
+}
+
+/// Counts the square roots of n modulo the number with the given factorization
+fn count_square_roots(n: u64, factors: &[(u64, u32)]) -> usize {
+    factors
+        .iter()
+        .map(|&(q, e)| square_roots_prime_power(n, q, e).0.len())
+        .product()
+}
+
+/// Lists the values with exactly one square root modulo m
+///
+/// Modulo an odd prime every nonzero residue has the two roots r and p - r,
+/// so only 0 qualifies (modulo 2, 1 is its own negation and qualifies too).
+/// Composite moduli are less regular: modulo 6, for example, 3 is the only
+/// square of 3, while modulo 4 and 9 even 0 has several roots.
+///
+/// # Arguments
+/// * `m` - The modulus (must be positive, need not be prime)
+///
+/// # Returns
+/// All n in 0..m with exactly one square root modulo m, in ascending order
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::values_with_single_root;
+///
+/// assert_eq!(values_with_single_root(7), vec![0]);
+/// assert_eq!(values_with_single_root(6), vec![0, 3]);
+/// assert_eq!(values_with_single_root(9), vec![]);
+/// ```
+pub fn values_with_single_root(m: u64) -> Vec<u64> {
+    if m == 0 {
+        panic!();
+    }
+
+    let factors = factorize(m);
+    (0..m)
+        .filter(|&n| count_square_roots(n, &factors) == 1)
+        .collect()
+}
+
+/// Checks whether squaring permutes the quadratic residues modulo the odd prime p
+///
+/// The nonzero residues form a cyclic group of order (p - 1) / 2, and
+/// squaring is a bijection on it exactly when that order is odd, that is
+/// when p ≡ 3 (mod 4). Each residue then has exactly one square root that
+/// is itself a residue, see `principal_sqrt_p3mod4`.
+///
+/// # Arguments
+/// * `p` - The odd prime modulus
+///
+/// # Returns
+/// `true` if p ≡ 3 (mod 4)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::is_sqrt_bijective;
+///
+/// assert!(is_sqrt_bijective(7));
+/// assert!(!is_sqrt_bijective(17));
+/// ```
+pub fn is_sqrt_bijective(p: u64) -> bool {
+    p % 4 == 3
+}
+
+/// Computes the square root of n modulo p that is itself a quadratic residue
+///
+/// For p ≡ 3 (mod 4), -1 is a non-residue, so exactly one of the roots r
+/// and p - r is a residue. It is n^((p+1)/4), a power of the residue n.
+/// Unlike `tonelli_shanks`, this root is not necessarily the smaller one.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus, which must be ≡ 3 (mod 4)
+///
+/// # Returns
+/// * `Some(r)` if n is a quadratic residue, where r is the root that is a residue
+/// * `None` if n is not a quadratic residue or p is not ≡ 3 (mod 4)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::principal_sqrt_p3mod4;
+///
+/// // 2 ≡ 3² ≡ 4² (mod 7), and 4 = 2² is the root that is a residue
+/// assert_eq!(principal_sqrt_p3mod4(2, 7), Some(4));
+/// assert_eq!(principal_sqrt_p3mod4(3, 7), None);
+/// assert_eq!(principal_sqrt_p3mod4(2, 17), None);
+/// ```
+pub fn principal_sqrt_p3mod4(n: u64, p: u64) -> Option<u64> {
+    if !is_sqrt_bijective(p) {
+        return None;
+    }
+
+    let n_mod_p = n % p;
+    let r = pow_mod_u64(n_mod_p, p / 4 + 1, p);
+    (mul_mod(r, r, p) == n_mod_p).then_some(r)
+}
+
+/// Computes a square root of a/b modulo p, written as a small fraction when possible
+///
+/// The root r of a · b^(-1) is turned back into a fraction by rational
+/// reconstruction (Wang's algorithm): the extended Euclidean algorithm on
+/// (p, r) is stopped at the first remainder num ≤ N = ⌊√(p/2)⌋, and the
+/// matching cofactor gives den. If some x/y with 0 ≤ x ≤ N and 0 < y ≤ N is
+/// a root, this finds it, and it is unique since 2N² < p. As -x/y is the
+/// other root, one of the two always has a non-negative numerator.
+///
+/// # Arguments
+/// * `a` - The numerator of the number to find the square root of
+/// * `b` - The denominator, which must not be ≡ 0 (mod p)
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some((num, den))` where num · den^(-1) is a root of a/b and num, den ≤ N
+/// * `Some((r, 1))` with the canonical root r if no such fraction exists
+/// * `None` if a/b is not a quadratic residue or b ≡ 0 (mod p)
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::modular_sqrt_rational_repr;
+///
+/// // (3/7)² = 9/49
+/// assert_eq!(modular_sqrt_rational_repr(9, 49, 1000003), Some((3, 7)));
+/// assert_eq!(modular_sqrt_rational_repr(2, 1, 7), Some((3, 1)));
+/// ```
+pub fn modular_sqrt_rational_repr(a: u64, b: u64, p: u64) -> Option<(u64, u64)> {
+    let b_inverse = mod_inverse(b % p, p)?;
+    let r = tonelli_shanks_u64(mul_mod(a % p, b_inverse, p), p)?;
+
+    let bound = (p / 2).isqrt();
+    let (mut r0, mut r1) = (p as i128, r as i128);
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 > bound as i128 {
+        let quotient = r0 / r1;
+        (r0, r1) = (r1, r0 - quotient * r1);
+        (t0, t1) = (t1, t0 - quotient * t1);
+    }
+
+    // r1 ≡ t1 · r (mod p), so r1 / |t1| is r or -r
+    let (num, den) = (r1 as u64, t1.unsigned_abs() as u64);
+    if den != 0 && den <= bound && gcd(num, den) == 1 {
+        Some((num, den))
+    } else {
+        Some((r, 1))
+    }
+}
+
+/// Computes the greatest common divisor of a and b
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Describes the minimal polynomial of √n over the rationals
+///
+/// √n is rational exactly when n is a perfect square k², and then its
+/// minimal polynomial is x - k. Otherwise it is x² - n, and writing
+/// n = k² · m with m squarefree gives √n = k·√m, which is appended when
+/// k > 1. Unlike the rest of the crate this is about integers, not
+/// residues, and is meant for teaching.
+///
+/// # Arguments
+/// * `n` - The number whose square root is described
+///
+/// # Returns
+/// The minimal polynomial as a string
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::minimal_polynomial_str;
+///
+/// assert_eq!(minimal_polynomial_str(7), "x^2 - 7");
+/// assert_eq!(minimal_polynomial_str(8), "x^2 - 8 (= 2·sqrt(2))");
+/// assert_eq!(minimal_polynomial_str(9), "x - 3");
+/// ```
+pub fn minimal_polynomial_str(n: u64) -> String {
+    if n == 0 {
+        return "x".to_string();
+    }
+
+    let (k, m) = factorize(n).into_iter().fold((1, 1), |(k, m), (q, e)| {
+        (k * q.pow(e / 2), m * q.pow(e % 2))
+    });
+
+    if m == 1 {
+        format!("x - {}", k)
+    } else if k == 1 {
+        format!("x^2 - {}", n)
+    } else {
+        format!("x^2 - {} (= {}·sqrt({}))", n, k, m)
+    }
+}
+
+/// Miller-Rabin bases that decide primality correctly for every `u64`
+///
+/// The first twelve primes have no common strong pseudoprime below
+/// 3.18 · 10^23, far above `u64::MAX`.
+pub const PRIME_WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Checks whether n is prime
+///
+/// Runs Miller-Rabin with `PRIME_WITNESSES`, which makes the answer exact
+/// for all `u64` rather than probabilistic.
+///
+/// # Arguments
+/// * `n` - The number to test
+///
+/// # Returns
+/// `true` if n is prime
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::is_prime;
+///
+/// assert!(is_prime(998244353));
+/// assert!(!is_prime(2047)); // 23 · 89
+/// ```
+pub fn is_prime(n: u64) -> bool {
+    is_prime_with_witnesses(n, &PRIME_WITNESSES)
+}
+
+/// Runs the Miller-Rabin test on n with the given bases
+///
+/// Fewer bases are faster but may let strong pseudoprimes through, e.g.
+/// 2047 = 23 · 89 passes base 2 alone. Bases that are multiples of n say
+/// nothing about n and are skipped.
+///
+/// # Arguments
+/// * `n` - The number to test
+/// * `witnesses` - The Miller-Rabin bases to try
+///
+/// # Returns
+/// * `false` if n < 2, n is even and not 2, or some base proves n composite
+/// * `true` otherwise, meaning n is prime or a strong pseudoprime to every base
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::is_prime_with_witnesses;
+///
+/// assert!(is_prime_with_witnesses(2047, &[2]));
+/// assert!(!is_prime_with_witnesses(2047, &[2, 3]));
+/// ```
+pub fn is_prime_with_witnesses(n: u64, witnesses: &[u64]) -> bool {
+    if n < 2 {
+        return false;
+    }
+    if n.is_multiple_of(2) {
+        return n == 2;
+    }
+
+    let (s, d) = split_two_adic(n);
+    witnesses.iter().all(|&a| {
+        let a = a % n;
+        if a == 0 {
+            return true;
+        }
+
+        let mut x = pow_mod_u64(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Computes the distance |p - 2r| between the two square roots r and p - r of n modulo p
+///
+/// # Arguments
+/// * `n` - The number whose roots are compared
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(gap)` if n is a quadratic residue modulo p, 0 when n ≡ 0
+/// * `None` if n is not a quadratic residue modulo p
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::root_gap;
+///
+/// assert_eq!(root_gap(2, 7), Some(1)); // roots 3 and 4
+/// assert_eq!(root_gap(0, 7), Some(0));
+/// assert_eq!(root_gap(3, 7), None);
+/// ```
+pub fn root_gap(n: u64, p: u64) -> Option<u64> {
+    square_roots_u64(n, p).map(|(r1, r2)| r2 - r1)
+}
+
+/// Computes a primitive 2^log_n-th root of unity modulo p for a number-theoretic transform
+///
+/// Raises the smallest primitive root g to (p - 1) / 2^log_n, so the result
+/// matches the usual NTT tables, e.g. 3^119 for 998244353 = 119 · 2^23 + 1.
+///
+/// # Arguments
+/// * `p` - The prime modulus
+/// * `log_n` - The base-2 logarithm of the transform length
+///
+/// # Returns
+/// * `Some(w)` with w^(2^log_n) ≡ 1 and w^(2^(log_n - 1)) ≢ 1 (mod p)
+/// * `None` if 2^log_n does not divide p - 1, or p < 2
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::ntt_root_of_unity;
+///
+/// assert_eq!(ntt_root_of_unity(998244353, 23), Some(15311432));
+/// assert_eq!(ntt_root_of_unity(17, 2), Some(13)); // 3^4
+/// assert_eq!(ntt_root_of_unity(17, 5), None);
+/// ```
+pub fn ntt_root_of_unity(p: u64, log_n: u32) -> Option<u64> {
+    if p < 2 || log_n > (p - 1).trailing_zeros() {
+        return None;
+    }
+
+    let g = primitive_root(p)?;
+    Some(pow_mod_u64(g, (p - 1) >> log_n, p))
+}
+
+/// The square roots of n modulo m, both per prime power of m and combined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootSet {
+    /// (q^e, roots modulo q^e in ascending order) for each prime power in m
+    pub per_prime: Vec<(u64, Vec<u64>)>,
+    /// Every root modulo m in ascending order, as `all_square_roots(n, m)`
+    pub combined: Vec<u64>,
+}
+
+/// Computes every square root of n modulo m together with the roots modulo each prime power
+///
+/// By the Chinese remainder theorem each root modulo m corresponds to
+/// exactly one choice of root modulo every q^e, so `combined` has as many
+/// entries as the product of the `per_prime` list lengths.
+///
+/// # Arguments
+/// * `n` - The number to find the square roots of
+/// * `m` - The modulus (must be positive, need not be prime)
+///
+/// # Returns
+/// The per-prime-power and combined roots
+///
+/// # Panics
+/// Panics if m is 0
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::roots_structured;
+///
+/// let roots = roots_structured(4, 45);
+/// assert_eq!(roots.per_prime, vec![(9, vec![2, 7]), (5, vec![2, 3])]);
+/// assert_eq!(roots.combined, vec![2, 7, 38, 43]);
+/// ```
+pub fn roots_structured(n: u64, m: u64) -> RootSet {
+    if m == 0 {
+        panic!();
+    }
+
+    let per_prime: Vec<(u64, Vec<u64>)> = factorize(m)
+        .into_iter()
+        .map(|(q, e)| {
+            let (mut roots, q_e) = square_roots_prime_power(n, q, e);
+            roots.sort_unstable();
+            (q_e, roots)
+        })
+        .collect();
+
+    let mut combined = combine_roots(per_prime.iter().map(|(q_e, roots)| (roots.clone(), *q_e)))
+        .expect("the prime powers of m are pairwise coprime");
+    combined.sort_unstable();
+
+    RootSet {
+        per_prime,
+        combined,
+    }
+}
+
+/// Computes a square root of n modulo p with a table-driven discrete logarithm
+///
+/// The classic main loop recomputes c^(2^(m-i-1)) and the order of t on
+/// every iteration, which costs O(s²) multiplications for p - 1 = 2^s · q.
+/// Here t = n^q is written as c^x by a divide-and-conquer discrete
+/// logarithm in the group of order 2^s generated by c = z^q: the low half
+/// of the bits of x is found in a subgroup after squaring t, removed using
+/// a precomputed table of c^(-2^j), and the high half found recursively.
+/// That is O(s log s) multiplications, and then r = n^((q+1)/2) · c^(-x/2).
+/// The gain only shows for primes with large s.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// * `Some(r)` with r² ≡ n (mod p) and r ≤ p - r, the same root as `tonelli_shanks`
+/// * `None` if n is not a quadratic residue modulo p
+///
+/// # Panics
+/// Panics if p is 0 or even and not 2
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::modular_sqrt_fast_s;
+///
+/// // 998244353 - 1 = 2^23 · 119
+/// assert_eq!(modular_sqrt_fast_s(2, 998244353), Some(116195171));
+/// assert_eq!(modular_sqrt_fast_s(3, 998244353), None);
+/// ```
+pub fn modular_sqrt_fast_s(n: u64, p: u64) -> Option<u64> {
+    modular_sqrt_fast_s_opcount(n, p).0
+}
+
+/// Computes `modular_sqrt_fast_s(n, p)` along with the field operations used
+///
+/// Counts the same way as `modular_sqrt_opcount`, so the two can be
+/// compared directly.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+/// * `p` - The prime modulus
+///
+/// # Returns
+/// The result of `modular_sqrt_fast_s(n, p)` together with an `OpCount`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::{modular_sqrt_fast_s_opcount, modular_sqrt_opcount};
+///
+/// // 27 · 2^56 + 1 is prime
+/// let p = 1945555039024054273;
+/// let (root, fast) = modular_sqrt_fast_s_opcount(6, p);
+/// let (expected, naive) = modular_sqrt_opcount(6, p);
+/// assert_eq!(root, expected);
+/// assert!(fast.mults + fast.sqrs < naive.mults + naive.sqrs);
+/// ```
+pub fn modular_sqrt_fast_s_opcount(n: u64, p: u64) -> (Option<u64>, OpCount) {
+    let mut ops = OpCount::default();
+
+    if p == 2 {
+        return (Some(n % 2), ops);
+    }
+    if p.is_multiple_of(2) {
+        panic!();
+    }
+
+    let n_mod_p = n % p;
+    if n_mod_p == 0 {
+        return (Some(0), ops);
+    }
+    if legendre_symbol_counted::<true>(n_mod_p, p, &mut ops) != 1 {
+        return (None, ops);
+    }
+
+    let (s, q) = split_two_adic(p);
+    // w = n^((q-1)/2) gives both r = n^((q+1)/2) and t = n^q
+    let w = pow_mod_counted::<true>(n_mod_p, q / 2, p, &mut ops);
+    let mut r = mul_mod(w, n_mod_p, p);
+    let t = mul_mod(w, r, p);
+    ops.mults += 2;
+
+    if s > 1 {
+        let Some(z) = find_quadratic_non_residue_counted::<true>(p, &mut ops) else {
+            return (None, ops);
+        };
+        let c = pow_mod_counted::<true>(z, q, p, &mut ops);
+
+        // inverse_powers[j] = c^(-2^j) for j < s, with c^(-1) = c^(2^s - 1)
+        let mut inverse_powers = vec![pow_mod_counted::<true>(c, (1 << s) - 1, p, &mut ops)];
+        for j in 1..s as usize {
+            let last = inverse_powers[j - 1];
+            inverse_powers.push(mul_mod(last, last, p));
+            ops.sqrs += 1;
+        }
+
+        // t = c^x with x even, since n is a residue
+        let Some(x) = discrete_log_prime_power::<true>(t, 2, s, s, &inverse_powers, p, &mut ops)
+        else {
+            return (None, ops);
+        };
+        for j in (1..s).filter(|j| x >> j & 1 == 1) {
+            r = mul_mod(r, inverse_powers[j as usize - 1], p);
+            ops.mults += 1;
+        }
+    }
+
+    if mul_mod(r, r, p) != n_mod_p {
+        // Only possible for a modulus that is not prime
+        return (None, ops);
+    }
+    (Some(canonical_root(r, p)), ops)
+}
+
+/// Collects the relations of a quadratic sieve for n over a factor base
+///
+/// Sieves Q(a) = a² - n for the `sieve_len` values a = ⌈√n⌉, ⌈√n⌉ + 1, ...
+/// A prime p divides Q(a) exactly when a ≡ ±√n (mod p), so the roots from
+/// Tonelli-Shanks give the positions to divide at, every p-th value from
+/// each root. Primes modulo which n is not a residue never divide any Q(a)
+/// and are skipped. Values left at 1 after all primes are divided out
+/// completely are smooth over the factor base.
+///
+/// # Arguments
+/// * `n` - The number to factor
+/// * `factor_base` - The primes to sieve with
+/// * `sieve_len` - The number of values of a to sieve
+///
+/// # Returns
+/// The smooth a in ascending order, each with the exponents of the
+/// factor-base primes in a² - n, in the order of `factor_base`
+///
+/// # Panics
+/// Panics if a factor-base entry is 0 or 1, which would divide every value
+/// forever, or even and not 2
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::collect_smooth_relations;
+///
+/// // ⌈√15347⌉ = 124 and 124² - 15347 = 29
+/// let relations = collect_smooth_relations(15347, &[2, 17, 23, 29], 100);
+/// assert_eq!(relations[0], (124, vec![0, 0, 0, 1]));
+/// assert_eq!(relations[2], (127, vec![1, 1, 1, 0])); // 782 = 2 · 17 · 23
+/// ```
+pub fn collect_smooth_relations(
+    n: u64,
+    factor_base: &[u64],
+    sieve_len: u64,
+) -> Vec<(u64, Vec<u32>)> {
+    let root = n.isqrt();
+    let start = if root * root == n { root } else { root + 1 };
+
+    let mut remaining: Vec<u128> = (0..sieve_len)
+        .map(|i| {
+            let a = (start + i) as u128;
+            a * a - n as u128
+        })
+        .collect();
+    let mut exponents = vec![vec![0u32; factor_base.len()]; sieve_len as usize];
+
+    for (k, &p) in factor_base.iter().enumerate() {
+        assert!(p >= 2, "factor-base entry {p} is not a prime");
+        let Some((r1, r2)) = square_roots_u64(n, p) else {
+            continue;
+        };
+
+        let roots = if r1 == r2 { vec![r1] } else { vec![r1, r2] };
+        for r in roots {
+            // The first i with start + i ≡ r (mod p)
+            let first = ((r as u128 + p as u128 - (start % p) as u128) % p as u128) as u64;
+            for i in (first..sieve_len).step_by(p as usize) {
+                let value = &mut remaining[i as usize];
+                // Q(a) = 0 only when n is a perfect square, and has no factorization
+                while *value != 0 && value.is_multiple_of(p as u128) {
+                    *value /= p as u128;
+                    exponents[i as usize][k] += 1;
+                }
+            }
+        }
+    }
+
+    (0..sieve_len)
+        .filter(|&i| remaining[i as usize] == 1)
+        .map(|i| (start + i, exponents[i as usize].clone()))
+        .collect()
+}
+
+/// Computes the canonical square root of n modulo a compile-time prime P in a const context
+///
+/// Runs the same `const fn` Tonelli-Shanks core as `tonelli_shanks`,
+/// including the non-residue search, so roots (or whole tables of them)
+/// can be computed at compile time and baked into firmware. It uses no
+/// heap and only bounded loops: the search tries at most P - 2 candidates
+/// and the main loop at most s² squarings for P - 1 = 2^s · q. The
+/// compiler warns when const evaluation runs for a long time, so very
+/// large P with many inputs may be slow to build. Unlike `tonelli_shanks`
+/// there is no fallback, so a composite P yields `None` where the main
+/// loop fails. It can also be called at runtime.
+///
+/// # Arguments
+/// * `n` - The number to find the square root of
+///
+/// # Returns
+/// * `Some(r)` with r² ≡ n (mod P) and r ≤ P - r, the root of `tonelli_shanks_u64(n, P)`
+/// * `None` if n is not a quadratic residue modulo P
+///
+/// # Panics
+/// Panics, or fails to compile in a const context, if P is 0 or even and not 2
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::sqrt_const;
+///
+/// const ROOT: Option<u64> = sqrt_const::<7>(2);
+/// assert_eq!(ROOT, Some(3));
+///
+/// // All square roots modulo 17, computed at compile time
+/// const TABLE: [Option<u64>; 17] = {
+///     let mut table = [None; 17];
+///     let mut n = 0;
+///     while n < 17 {
+///         table[n] = sqrt_const::<17>(n as u64);
+///         n += 1;
+///     }
+///     table
+/// };
+/// assert_eq!(TABLE[2], Some(6));
+/// assert_eq!(TABLE[3], None);
+/// ```
+pub const fn sqrt_const<const P: u64>(n: u64) -> Option<u64> {
+    match tonelli_shanks_core::<false>(n, P, &mut Trace::new()) {
+        Some(r) => Some(canonical_root(r, P)),
+        None => None,
+    }
 }
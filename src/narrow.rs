@@ -0,0 +1,34 @@
+//! `u32` versions of the core functions.
+
+use crate::{OpCount, Trace, kernel, modular_sqrt_any};
+
+/// Computes a * b mod p, multiplying in `u64`
+const fn mul_mod(a: u32, b: u32, p: u32) -> u32 {
+    (a as u64 * b as u64 % p as u64) as u32
+}
+
+kernel::tonelli_kernel!(u32);
+
+/// Computes a square root of n modulo p on `u32` using Tonelli-Shanks
+///
+/// Every intermediate fits in a `u64`, which makes this the cheapest width
+/// for primes below 2^32. Returns the canonical root, the smaller of r and
+/// p - r. A composite p falls back to `modular_sqrt_any` like
+/// `tonelli_shanks_u64`.
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::tonelli_shanks_u32;
+///
+/// assert_eq!(tonelli_shanks_u32(2, 7), Some(3));
+/// assert_eq!(tonelli_shanks_u32(3, 7), None);
+/// assert_eq!(tonelli_shanks_u32(9, 4294967291), Some(3));
+/// ```
+pub fn tonelli_shanks_u32(n: u32, p: u32) -> Option<u32> {
+    let mut trace = Trace::new();
+    let root = tonelli_shanks_core::<false>(n, p, &mut trace);
+    if trace.fallback {
+        return modular_sqrt_any(u64::from(n % p), u64::from(p)).map(|r| r as u32);
+    }
+    root.map(|r| canonical_root(r, p))
+}
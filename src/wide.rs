@@ -0,0 +1,104 @@
+//! `u128` versions of the core functions.
+
+use crate::{OpCount, Trace, kernel, modular_sqrt_any};
+
+/// Computes (a + b) mod p for a, b < p without overflowing
+const fn add_mod(a: u128, b: u128, p: u128) -> u128 {
+    let (sum, overflowed) = a.overflowing_add(b);
+    if overflowed || sum >= p {
+        sum.wrapping_sub(p)
+    } else {
+        sum
+    }
+}
+
+/// Computes a * b mod p without overflowing
+///
+/// Moduli below 2^64 multiply natively. Larger moduli have no wider native
+/// type to multiply into, so they fall back to double-and-add.
+const fn mul_mod(a: u128, b: u128, p: u128) -> u128 {
+    let (mut a, mut b) = (a % p, b % p);
+    if p <= u64::MAX as u128 {
+        return (a * b) % p;
+    }
+
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, p);
+        }
+        a = add_mod(a, a, p);
+        b >>= 1;
+    }
+    result
+}
+
+kernel::tonelli_kernel!(u128);
+
+/// Computes modular exponentiation x^n mod p on `u128`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::pow_mod_u128;
+///
+/// assert_eq!(pow_mod_u128(3, 5, 7), 5);
+/// assert_eq!(pow_mod_u128(2, 127, u128::MAX), 1 << 127);
+/// ```
+pub fn pow_mod_u128(x: u128, n: u128, p: u128) -> u128 {
+    pow_mod_counted::<false>(x, n, p, &mut OpCount::default())
+}
+
+/// Computes the Legendre symbol (a/p) on `u128`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::legendre_symbol_u128;
+///
+/// assert_eq!(legendre_symbol_u128(2, 7), 1);
+/// assert_eq!(legendre_symbol_u128(3, 7), -1);
+/// ```
+pub fn legendre_symbol_u128(a: u128, p: u128) -> i32 {
+    legendre_symbol_counted::<false>(a, p, &mut OpCount::default())
+}
+
+/// Computes a square root of n modulo p on `u128` using Tonelli-Shanks
+///
+/// Returns the canonical root, the smaller of r and p - r. If the main loop
+/// fails because p is composite, a p below 2^64 falls back to
+/// `modular_sqrt_any` like `tonelli_shanks_u64`; larger composites yield
+/// `None`.
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::tonelli_shanks_u128;
+///
+/// assert_eq!(tonelli_shanks_u128(2, 7), Some(3));
+/// assert_eq!(tonelli_shanks_u128(3, 7), None);
+///
+/// // 2^127 - 1 is prime
+/// let p = (1u128 << 127) - 1;
+/// assert_eq!(tonelli_shanks_u128(9, p), Some(3));
+/// ```
+pub fn tonelli_shanks_u128(n: u128, p: u128) -> Option<u128> {
+    let mut trace = Trace::new();
+    let root = tonelli_shanks_core::<false>(n, p, &mut trace);
+    if trace.fallback
+        && let (Ok(n), Ok(p)) = (u64::try_from(n % p), u64::try_from(p))
+    {
+        return modular_sqrt_any(n, p).map(u128::from);
+    }
+    root.map(|r| canonical_root(r, p))
+}
+
+/// Computes both square roots of n modulo p on `u128`
+///
+/// # Examples
+/// ```
+/// use tonelli_rs::square_roots_u128;
+///
+/// assert_eq!(square_roots_u128(2, 7), Some((3, 4)));
+/// assert_eq!(square_roots_u128(0, 7), Some((0, 0)));
+/// ```
+pub fn square_roots_u128(n: u128, p: u128) -> Option<(u128, u128)> {
+    tonelli_shanks_u128(n, p).map(|r| if r == 0 { (0, 0) } else { (r, p - r) })
+}
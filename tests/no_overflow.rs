@@ -0,0 +1,128 @@
+// Runs the u64 functions on moduli and inputs right below 2^64. The test
+// profile enables `overflow-checks`, so any intermediate that does not fit
+// in a u64 panics here instead of silently wrapping.
+use tonelli_rs::*;
+
+/// Primes just below 2^64, ≡ 3 (mod 4), ≡ 5 (mod 8) and ≡ 1 (mod 8)
+const LARGE_PRIMES: [u64; 6] = [
+    18446744073709551427,
+    18446744073709551359,
+    18446744073709551557,
+    18446744073709551533,
+    18446744073709551521,
+    18446744073709551337,
+];
+
+/// 2^64 - 2^32 + 1, where p - 1 = 2^32 · 3 · 5 · 17 · 257 · 65537
+const GOLDILOCKS: u64 = 18446744069414584321;
+
+/// Inputs near 0, near p and near 2^64
+fn large_inputs(p: u64) -> Vec<u64> {
+    let mut inputs: Vec<u64> = (0..8).collect();
+    inputs.extend((1..8).map(|k| p - k));
+    inputs.extend((0..8).map(|k| u64::MAX - k));
+    inputs.push(p);
+    inputs
+}
+
+#[test]
+fn test_no_overflow_arithmetic() {
+    for p in LARGE_PRIMES.into_iter().chain([GOLDILOCKS, u64::MAX]) {
+        for x in large_inputs(p) {
+            let expected = pow_mod_u128(x as u128, 2, p as u128) as u64;
+            assert_eq!(pow_mod_u64(x, 2, p), expected);
+            assert_eq!(square_mod(x, p), expected);
+            assert_eq!(
+                pow_mod_u64(x, u64::MAX, p),
+                pow_mod_u128(x as u128, u64::MAX as u128, p as u128) as u64
+            );
+            assert_eq!((neg_mod(x, p) as u128 + (x % p) as u128) % p as u128, 0);
+        }
+    }
+
+    let p = LARGE_PRIMES[0];
+    for x in large_inputs(p).into_iter().filter(|x| x % p != 0) {
+        let inverse = mod_inverse(x, p).unwrap();
+        assert_eq!(
+            pow_mod_u128(x as u128, 1, p as u128) * inverse as u128 % p as u128,
+            1
+        );
+    }
+
+    assert_eq!(
+        crt(&[(u64::MAX, 4294967291), (1, 4294967279)]).map(|x| (x % 4294967291, x % 4294967279)),
+        Some((u64::MAX % 4294967291, 1))
+    );
+}
+
+#[test]
+fn test_no_overflow_symbols() {
+    for p in LARGE_PRIMES.into_iter().chain([GOLDILOCKS]) {
+        for a in large_inputs(p) {
+            let expected = legendre_symbol_u128(a as u128, p as u128);
+            assert_eq!(legendre_symbol_u64(a, p), expected);
+            assert_eq!(jacobi_symbol(a, p), expected);
+        }
+    }
+}
+
+#[test]
+fn test_no_overflow_square_roots() {
+    for p in LARGE_PRIMES.into_iter().chain([GOLDILOCKS]) {
+        let field = PrimeField::new(p);
+        for n in large_inputs(p) {
+            let expected = tonelli_shanks_u128(n as u128, p as u128).map(|r| r as u64);
+            assert_eq!(tonelli_shanks_u64(n, p), expected, "n = {n}, p = {p}");
+            assert_eq!(cipolla(n, p), expected);
+            assert_eq!(modular_sqrt(n, p), expected);
+            assert_eq!(field.sqrt(n), expected);
+            assert_eq!(tonelli_shanks_flagged(n, p), (expected, false));
+            assert_eq!(
+                tonelli_shanks_certificate(n, p).is_some(),
+                expected.is_some()
+            );
+            assert_eq!(square_root_min(n, p), expected);
+            assert_eq!(
+                square_roots_u64(n, p),
+                square_roots_u128(n as u128, p as u128).map(|(a, b)| (a as u64, b as u64))
+            );
+            assert_eq!(field.nth_power_of_two_root(n, 1), expected);
+        }
+    }
+}
+
+#[test]
+fn test_no_overflow_smooth_prime() {
+    let p = GOLDILOCKS;
+    let factors = factorize(p - 1);
+    assert_eq!(
+        factors,
+        vec![(2, 32), (3, 1), (5, 1), (17, 1), (257, 1), (65537, 1)]
+    );
+    assert!(is_primitive_root(7, p));
+
+    for target in large_inputs(p).into_iter().filter(|x| x % p != 0) {
+        let x = discrete_log_ph(7, target, p, &factors).unwrap();
+        assert_eq!(pow_mod_u64(7, x, p), target % p);
+    }
+
+    // A 2^32-th root of unity and its powers
+    let field = PrimeField::new(p);
+    let root = field.unity_tower()[32];
+    assert_eq!(pow_mod_u64(root, 1 << 32, p), 1);
+    assert_eq!(pow_mod_u64(root, 1 << 31, p), p - 1);
+}
+
+#[test]
+fn test_no_overflow_gaussian() {
+    let p = LARGE_PRIMES[0];
+    for (a, b) in [(3, 4), (p - 1, 0), (u64::MAX, 12345), (p - 2, p - 3)] {
+        if let Some((c, d)) = sqrt_gaussian(a, b, p) {
+            let real = (pow_mod_u128(c as u128, 2, p as u128) + p as u128
+                - pow_mod_u128(d as u128, 2, p as u128))
+                % p as u128;
+            let imaginary = 2 * c as u128 % p as u128 * d as u128 % p as u128;
+            assert_eq!((real as u64, imaginary as u64), (a % p, b % p));
+        }
+    }
+}
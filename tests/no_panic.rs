@@ -0,0 +1,74 @@
+// Calls every function of the non-panicking surface listed in the crate
+// docs on adversarial inputs and asserts that none of them panics.
+use std::panic::{UnwindSafe, catch_unwind};
+
+use tonelli_rs::*;
+
+/// Zero, one, two, even numbers, odd composites, primes and the extremes of u64
+const INPUTS: [u64; 17] = [
+    0,
+    1,
+    2,
+    3,
+    4,
+    7,
+    8,
+    9,
+    15,
+    17,
+    21,
+    85,
+    1000001,
+    1000003,
+    18446744073709551557,
+    u64::MAX - 1,
+    u64::MAX,
+];
+
+/// Asserts that f does not panic on any pair of inputs
+fn assert_no_panic<T>(name: &str, f: impl Fn(u64, u64) -> T + Copy + UnwindSafe) {
+    for a in INPUTS {
+        for b in INPUTS {
+            assert!(
+                catch_unwind(move || {
+                    f(a, b);
+                })
+                .is_ok(),
+                "{name}({a}, {b}) panicked"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_checked_functions_never_panic() {
+    assert_no_panic("pow_mod_checked", |a, b| pow_mod_checked(a, b, b));
+    assert_no_panic("pow_mod_checked", |a, b| pow_mod_checked(b, a, a));
+    assert_no_panic("legendre_symbol_checked", legendre_symbol_checked);
+    assert_no_panic("tonelli_shanks_checked", tonelli_shanks_checked);
+    assert_no_panic("square_roots_checked", square_roots_checked);
+    assert_no_panic("PrimeField::from_parts", PrimeField::from_parts);
+}
+
+#[test]
+fn test_option_functions_never_panic() {
+    assert_no_panic("mod_inverse", mod_inverse);
+    assert_no_panic("crt", |a, b| crt(&[(a, b), (b, a)]));
+    assert_no_panic("crt", |a, b| crt(&[(a, b), (a, b)]));
+    assert_no_panic("crt_check", |a, b| crt_check(&[(a, b), (b, a)]));
+    assert_no_panic("sqrt_gaussian", |a, b| sqrt_gaussian(a, a, b));
+    assert_no_panic("sqrt_gaussian", |a, b| sqrt_gaussian(a, 0, b));
+    assert_no_panic("principal_sqrt_p3mod4", principal_sqrt_p3mod4);
+}
+
+#[test]
+fn test_invalid_input_is_reported() {
+    // 9 has no quadratic non-residue by Euler's criterion, which used to
+    // reach an `unreachable!`
+    assert!(tonelli_shanks_checked(1, 9).is_ok());
+    assert!(square_roots_checked(8, 21).is_ok());
+    assert_eq!(square_roots_checked(2, 0), Err(TonelliError::ModulusZero));
+    assert_eq!(mod_inverse(3, 0), None);
+    assert_eq!(crt(&[(1, 3), (2, 0)]), None);
+    assert_eq!(crt_check(&[(1, 0)]), None);
+}
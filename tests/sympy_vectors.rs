@@ -0,0 +1,362 @@
+// Square roots cross-checked against sympy, generated with
+// `sympy.ntheory.sqrt_mod(n, m, all_roots=True)` (sympy 1.14), sorted.
+use tonelli_rs::*;
+
+/// (n, p, roots) for primes p ≡ 3 (mod 4), p ≡ 5 (mod 8) and p ≡ 1 (mod 8)
+const PRIME_VECTORS: &[(u64, u64, &[u64])] = &[
+    (0, 2, &[0]),
+    (1, 2, &[1]),
+    (2, 2, &[0]),
+    (3, 2, &[1]),
+    (0, 3, &[0]),
+    (1, 3, &[1, 2]),
+    (2, 3, &[]),
+    (3, 3, &[0]),
+    (0, 7, &[0]),
+    (1, 7, &[1, 6]),
+    (2, 7, &[3, 4]),
+    (3, 7, &[]),
+    (4, 7, &[2, 5]),
+    (6, 7, &[]),
+    (0, 11, &[0]),
+    (1, 11, &[1, 10]),
+    (2, 11, &[]),
+    (3, 11, &[5, 6]),
+    (8, 11, &[]),
+    (10, 11, &[]),
+    (0, 19, &[0]),
+    (1, 19, &[1, 18]),
+    (2, 19, &[]),
+    (3, 19, &[]),
+    (4, 19, &[2, 17]),
+    (7, 19, &[8, 11]),
+    (14, 19, &[]),
+    (15, 19, &[]),
+    (18, 19, &[]),
+    (0, 23, &[0]),
+    (1, 23, &[1, 22]),
+    (2, 23, &[5, 18]),
+    (3, 23, &[7, 16]),
+    (11, 23, &[]),
+    (17, 23, &[]),
+    (22, 23, &[]),
+    (0, 1000003, &[0]),
+    (1, 1000003, &[1, 1000002]),
+    (2, 1000003, &[]),
+    (3, 1000003, &[]),
+    (179291, 1000003, &[]),
+    (211431, 1000003, &[]),
+    (454080, 1000003, &[29723, 970280]),
+    (954313, 1000003, &[]),
+    (1000002, 1000003, &[]),
+    (0, 5, &[0]),
+    (1, 5, &[1, 4]),
+    (2, 5, &[]),
+    (3, 5, &[]),
+    (4, 5, &[2, 3]),
+    (0, 13, &[0]),
+    (1, 13, &[1, 12]),
+    (2, 13, &[]),
+    (3, 13, &[4, 9]),
+    (5, 13, &[]),
+    (7, 13, &[]),
+    (10, 13, &[6, 7]),
+    (12, 13, &[5, 8]),
+    (0, 29, &[0]),
+    (1, 29, &[1, 28]),
+    (2, 29, &[]),
+    (3, 29, &[]),
+    (19, 29, &[]),
+    (28, 29, &[12, 17]),
+    (0, 37, &[0]),
+    (1, 37, &[1, 36]),
+    (2, 37, &[]),
+    (3, 37, &[15, 22]),
+    (9, 37, &[3, 34]),
+    (30, 37, &[17, 20]),
+    (36, 37, &[6, 31]),
+    (0, 53, &[0]),
+    (1, 53, &[1, 52]),
+    (2, 53, &[]),
+    (3, 53, &[]),
+    (15, 53, &[11, 42]),
+    (21, 53, &[]),
+    (26, 53, &[]),
+    (29, 53, &[20, 33]),
+    (52, 53, &[23, 30]),
+    (0, 1000037, &[0]),
+    (1, 1000037, &[1, 1000036]),
+    (2, 1000037, &[]),
+    (3, 1000037, &[]),
+    (44774, 1000037, &[]),
+    (374232, 1000037, &[37275, 962762]),
+    (633925, 1000037, &[]),
+    (912430, 1000037, &[474915, 525122]),
+    (1000036, 1000037, &[320900, 679137]),
+    (0, 17, &[0]),
+    (1, 17, &[1, 16]),
+    (2, 17, &[6, 11]),
+    (3, 17, &[]),
+    (5, 17, &[]),
+    (7, 17, &[]),
+    (13, 17, &[8, 9]),
+    (16, 17, &[4, 13]),
+    (0, 41, &[0]),
+    (1, 41, &[1, 40]),
+    (2, 41, &[17, 24]),
+    (3, 41, &[]),
+    (13, 41, &[]),
+    (16, 41, &[4, 37]),
+    (19, 41, &[]),
+    (40, 41, &[9, 32]),
+    (0, 73, &[0]),
+    (1, 73, &[1, 72]),
+    (2, 73, &[32, 41]),
+    (3, 73, &[21, 52]),
+    (27, 73, &[10, 63]),
+    (34, 73, &[]),
+    (60, 73, &[]),
+    (69, 73, &[19, 54]),
+    (72, 73, &[27, 46]),
+    (0, 97, &[0]),
+    (1, 97, &[1, 96]),
+    (2, 97, &[14, 83]),
+    (3, 97, &[10, 87]),
+    (16, 97, &[4, 93]),
+    (30, 97, &[]),
+    (47, 97, &[12, 85]),
+    (51, 97, &[]),
+    (96, 97, &[22, 75]),
+    (0, 257, &[0]),
+    (1, 257, &[1, 256]),
+    (2, 257, &[60, 197]),
+    (3, 257, &[]),
+    (105, 257, &[]),
+    (116, 257, &[87, 170]),
+    (132, 257, &[]),
+    (169, 257, &[13, 244]),
+    (256, 257, &[16, 241]),
+    (0, 7681, &[0]),
+    (1, 7681, &[1, 7680]),
+    (2, 7681, &[3138, 4543]),
+    (3, 7681, &[316, 7365]),
+    (2967, 7681, &[2866, 4815]),
+    (3704, 7681, &[]),
+    (4604, 7681, &[]),
+    (7142, 7681, &[2152, 5529]),
+    (7680, 7681, &[3383, 4298]),
+    (0, 65537, &[0]),
+    (1, 65537, &[1, 65536]),
+    (2, 65537, &[4080, 61457]),
+    (3, 65537, &[]),
+    (22312, 65537, &[]),
+    (30705, 65537, &[8504, 57033]),
+    (62794, 65537, &[]),
+    (65287, 65537, &[]),
+    (65536, 65537, &[256, 65281]),
+    (0, 998244353, &[0]),
+    (1, 998244353, &[1, 998244352]),
+    (2, 998244353, &[116195171, 882049182]),
+    (3, 998244353, &[]),
+    (532582264, 998244353, &[344778242, 653466111]),
+    (822298834, 998244353, &[]),
+    (928640580, 998244353, &[140614574, 857629779]),
+    (943200281, 998244353, &[]),
+    (998244352, 998244353, &[86583718, 911660635]),
+];
+
+/// (n, m, roots) for composite moduli
+const COMPOSITE_VECTORS: &[(u64, u64, &[u64])] = &[
+    (0, 4, &[0, 2]),
+    (1, 4, &[1, 3]),
+    (2, 4, &[]),
+    (3, 4, &[]),
+    (4, 4, &[0, 2]),
+    (7, 4, &[]),
+    (0, 8, &[0, 4]),
+    (1, 8, &[1, 3, 5, 7]),
+    (3, 8, &[]),
+    (4, 8, &[2, 6]),
+    (5, 8, &[]),
+    (7, 8, &[]),
+    (0, 9, &[0, 3, 6]),
+    (1, 9, &[1, 8]),
+    (4, 9, &[2, 7]),
+    (5, 9, &[]),
+    (7, 9, &[4, 5]),
+    (8, 9, &[]),
+    (0, 12, &[0, 6]),
+    (1, 12, &[1, 5, 7, 11]),
+    (4, 12, &[2, 4, 8, 10]),
+    (7, 12, &[]),
+    (9, 12, &[3, 9]),
+    (11, 12, &[]),
+    (0, 15, &[0]),
+    (1, 15, &[1, 4, 11, 14]),
+    (4, 15, &[2, 7, 8, 13]),
+    (7, 15, &[]),
+    (13, 15, &[]),
+    (14, 15, &[]),
+    (0, 16, &[0, 4, 8, 12]),
+    (1, 16, &[1, 7, 9, 15]),
+    (4, 16, &[2, 6, 10, 14]),
+    (7, 16, &[]),
+    (14, 16, &[]),
+    (15, 16, &[]),
+    (0, 21, &[0]),
+    (1, 21, &[1, 8, 13, 20]),
+    (4, 21, &[2, 5, 16, 19]),
+    (5, 21, &[]),
+    (7, 21, &[7, 14]),
+    (20, 21, &[]),
+    (0, 27, &[0, 9, 18]),
+    (1, 27, &[1, 26]),
+    (4, 27, &[2, 25]),
+    (7, 27, &[13, 14]),
+    (21, 27, &[]),
+    (22, 27, &[7, 20]),
+    (26, 27, &[]),
+    (0, 45, &[0, 15, 30]),
+    (1, 45, &[1, 19, 26, 44]),
+    (3, 45, &[]),
+    (4, 45, &[2, 7, 38, 43]),
+    (7, 45, &[]),
+    (16, 45, &[4, 14, 31, 41]),
+    (42, 45, &[]),
+    (44, 45, &[]),
+    (0, 49, &[0, 7, 14, 21, 28, 35, 42]),
+    (1, 49, &[1, 48]),
+    (4, 49, &[2, 47]),
+    (7, 49, &[]),
+    (39, 49, &[23, 26]),
+    (40, 49, &[]),
+    (46, 49, &[12, 37]),
+    (48, 49, &[]),
+    (0, 60, &[0, 30]),
+    (1, 60, &[1, 11, 19, 29, 31, 41, 49, 59]),
+    (4, 60, &[2, 8, 22, 28, 32, 38, 52, 58]),
+    (7, 60, &[]),
+    (14, 60, &[]),
+    (34, 60, &[]),
+    (42, 60, &[]),
+    (59, 60, &[]),
+    (0, 63, &[0, 21, 42]),
+    (1, 63, &[1, 8, 55, 62]),
+    (4, 63, &[2, 16, 47, 61]),
+    (7, 63, &[14, 49]),
+    (42, 63, &[]),
+    (45, 63, &[]),
+    (46, 63, &[19, 26, 37, 44]),
+    (62, 63, &[]),
+    (0, 100, &[0, 10, 20, 30, 40, 50, 60, 70, 80, 90]),
+    (1, 100, &[1, 49, 51, 99]),
+    (4, 100, &[2, 48, 52, 98]),
+    (7, 100, &[]),
+    (11, 100, &[]),
+    (25, 100, &[5, 15, 25, 35, 45, 55, 65, 75, 85, 95]),
+    (34, 100, &[]),
+    (99, 100, &[]),
+    (0, 105, &[0]),
+    (1, 105, &[1, 29, 34, 41, 64, 71, 76, 104]),
+    (4, 105, &[2, 23, 37, 47, 58, 68, 82, 103]),
+    (7, 105, &[]),
+    (39, 105, &[12, 33, 72, 93]),
+    (59, 105, &[]),
+    (86, 105, &[]),
+    (104, 105, &[]),
+    (0, 121, &[0, 11, 22, 33, 44, 55, 66, 77, 88, 99, 110]),
+    (1, 121, &[1, 120]),
+    (4, 121, &[2, 119]),
+    (7, 121, &[]),
+    (37, 121, &[20, 101]),
+    (38, 121, &[37, 84]),
+    (107, 121, &[]),
+    (120, 121, &[]),
+    (0, 128, &[0, 16, 32, 48, 64, 80, 96, 112]),
+    (1, 128, &[1, 63, 65, 127]),
+    (4, 128, &[2, 30, 34, 62, 66, 94, 98, 126]),
+    (7, 128, &[]),
+    (24, 128, &[]),
+    (71, 128, &[]),
+    (127, 128, &[]),
+    (
+        0,
+        225,
+        &[
+            0, 15, 30, 45, 60, 75, 90, 105, 120, 135, 150, 165, 180, 195, 210,
+        ],
+    ),
+    (1, 225, &[1, 26, 199, 224]),
+    (4, 225, &[2, 52, 173, 223]),
+    (7, 225, &[]),
+    (92, 225, &[]),
+    (130, 225, &[]),
+    (215, 225, &[]),
+    (224, 225, &[]),
+    (0, 360, &[0, 60, 120, 180, 240, 300]),
+    (
+        1,
+        360,
+        &[
+            1, 19, 71, 89, 91, 109, 161, 179, 181, 199, 251, 269, 271, 289, 341, 359,
+        ],
+    ),
+    (4, 360, &[2, 38, 142, 178, 182, 218, 322, 358]),
+    (7, 360, &[]),
+    (237, 360, &[]),
+    (258, 360, &[]),
+    (260, 360, &[]),
+    (359, 360, &[]),
+    (0, 1001, &[0]),
+    (1, 1001, &[1, 155, 274, 428, 573, 727, 846, 1000]),
+    (4, 1001, &[2, 145, 310, 453, 548, 691, 856, 999]),
+    (7, 1001, &[]),
+    (189, 1001, &[]),
+    (373, 1001, &[]),
+    (777, 1001, &[]),
+    (1000, 1001, &[]),
+    (
+        0,
+        9801,
+        &[
+            0, 99, 198, 297, 396, 495, 594, 693, 792, 891, 990, 1089, 1188, 1287, 1386, 1485, 1584,
+            1683, 1782, 1881, 1980, 2079, 2178, 2277, 2376, 2475, 2574, 2673, 2772, 2871, 2970,
+            3069, 3168, 3267, 3366, 3465, 3564, 3663, 3762, 3861, 3960, 4059, 4158, 4257, 4356,
+            4455, 4554, 4653, 4752, 4851, 4950, 5049, 5148, 5247, 5346, 5445, 5544, 5643, 5742,
+            5841, 5940, 6039, 6138, 6237, 6336, 6435, 6534, 6633, 6732, 6831, 6930, 7029, 7128,
+            7227, 7326, 7425, 7524, 7623, 7722, 7821, 7920, 8019, 8118, 8217, 8316, 8415, 8514,
+            8613, 8712, 8811, 8910, 9009, 9108, 9207, 9306, 9405, 9504, 9603, 9702,
+        ],
+    ),
+    (1, 9801, &[1, 485, 9316, 9800]),
+    (4, 9801, &[2, 970, 8831, 9799]),
+    (7, 9801, &[]),
+    (470, 9801, &[]),
+    (2278, 9801, &[1099, 3761, 6040, 8702]),
+    (4808, 9801, &[]),
+    (9800, 9801, &[]),
+];
+
+#[test]
+fn test_sympy_prime_vectors() {
+    for &(n, p, expected) in PRIME_VECTORS {
+        assert_eq!(all_square_roots(n, p), expected, "n = {n}, p = {p}");
+        assert_eq!(
+            tonelli_shanks_u64(n, p),
+            expected.first().copied(),
+            "n = {n}, p = {p}"
+        );
+    }
+}
+
+#[test]
+fn test_sympy_composite_vectors() {
+    for &(n, m, expected) in COMPOSITE_VECTORS {
+        assert_eq!(all_square_roots(n, m), expected, "n = {n}, m = {m}");
+        assert_eq!(
+            modular_sqrt_any(n, m),
+            expected.first().copied(),
+            "n = {n}, m = {m}"
+        );
+    }
+}
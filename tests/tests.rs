@@ -1,3 +1,7 @@
+// These tests exercise the u64 API as a whole and run under every width
+// feature, see tests/width.rs for the tests written against `Int`.
+
+use tonelli_rs::compat::{legendre_symbol, pow_mod, square_roots, tonelli_shanks};
 use tonelli_rs::*;
 
 #[test]
@@ -28,13 +32,13 @@ fn test_find_quadratic_non_residue() {
 
 #[test]
 fn test_tonelli_shanks() {
-    assert_eq!(tonelli_shanks(2, 7), Some(4));
+    assert_eq!(tonelli_shanks(2, 7), Some(3));
     assert_eq!(tonelli_shanks(3, 7), None);
     assert_eq!(tonelli_shanks(4, 7), Some(2));
 
     assert_eq!(tonelli_shanks(2, 17), Some(6));
     assert_eq!(tonelli_shanks(3, 17), None);
-    assert_eq!(tonelli_shanks(9, 17), Some(14));
+    assert_eq!(tonelli_shanks(9, 17), Some(3));
 
     assert_eq!(tonelli_shanks(0, 7), Some(0));
     assert_eq!(tonelli_shanks(1, 7), Some(1));
@@ -55,15 +59,71 @@ fn test_square_roots() {
     assert_eq!(roots, Some((2, 5)));
 }
 
+#[test]
+fn test_tonelli_shanks_checked() {
+    assert_eq!(tonelli_shanks_checked(2, 7), Ok(Some(3)));
+    assert_eq!(tonelli_shanks_checked(3, 7), Ok(None));
+    assert_eq!(tonelli_shanks_checked(1, 2), Ok(Some(1)));
+    assert_eq!(tonelli_shanks_checked(2, 0), Err(TonelliError::ModulusZero));
+    assert_eq!(tonelli_shanks_checked(2, 8), Err(TonelliError::ModulusEven));
+}
+
+#[test]
+fn test_square_roots_checked() {
+    assert_eq!(square_roots_checked(2, 7), Ok(Some((3, 4))));
+    assert_eq!(square_roots_checked(2, 17), Ok(Some((6, 11))));
+    assert_eq!(square_roots_checked(3, 17), Ok(None));
+    assert_eq!(square_roots_checked(2, 0), Err(TonelliError::ModulusZero));
+    assert_eq!(square_roots_checked(2, 4), Err(TonelliError::ModulusEven));
+    assert_eq!(square_roots_checked(5, 100), Err(TonelliError::ModulusEven));
+}
+
+#[test]
+fn test_pinned_root_vectors() {
+    // These outputs are part of the crate's stability guarantee. If a change
+    // alters any of them, it changes which root callers receive.
+    let vectors = [
+        (2, 7, 3),
+        (4, 7, 2),
+        (2, 17, 6),
+        (4, 17, 2),
+        (3, 13, 4),
+        (4, 13, 2),
+        (2, 41, 17),
+        (4, 41, 2),
+        (2, 73, 32),
+        (3, 73, 21),
+        (2, 97, 14),
+        (3, 97, 10),
+        (2, 257, 60),
+        (4, 257, 2),
+        (2, 65537, 4080),
+        (4, 65537, 2),
+        (2, 998244353, 116195171),
+        (123456789, 998244353, 154095821),
+        (2, 1000000007, 59713600),
+        (3, 1000000007, 82062379),
+    ];
+
+    for (n, p, root) in vectors {
+        assert_eq!(tonelli_shanks(n, p), Some(root));
+        assert_eq!(tonelli_shanks_checked(n, p), Ok(Some(root)));
+        assert_eq!(tonelli_shanks_flagged(n, p), (Some(root), false));
+        assert_eq!(modular_sqrt_opcount(n, p).0, Some(root));
+        assert_eq!(cipolla(n, p), Some(root));
+        assert_eq!(square_roots(n, p), Some((root, p - root)));
+    }
+}
+
 #[test]
 fn test_large_prime() {
     let p = 1000000007;
     let n = 123456789;
 
-    if legendre_symbol(n, p) == 1 {
-        if let Some(r) = tonelli_shanks(n, p) {
-            assert_eq!((r * r) % p, n % p);
-        }
+    if legendre_symbol(n, p) == 1
+        && let Some(r) = tonelli_shanks(n, p)
+    {
+        assert_eq!((r * r) % p, n % p);
     }
 
     let n2 = 4;
@@ -71,3 +131,1882 @@ fn test_large_prime() {
         assert_eq!((r * r) % p, n2 % p);
     }
 }
+
+#[test]
+fn test_sqrt_fixed_points() {
+    assert_eq!(sqrt_fixed_points(2), vec![0, 1]);
+    assert_eq!(sqrt_fixed_points(7), vec![0, 1]);
+    assert_eq!(sqrt_fixed_points(101), vec![0, 1]);
+
+    assert_eq!(sqrt_fixed_points(6), vec![0, 1, 3, 4]);
+    assert_eq!(sqrt_fixed_points(10), vec![0, 1, 5, 6]);
+    assert_eq!(sqrt_fixed_points(30), vec![0, 1, 6, 10, 15, 16, 21, 25]);
+    assert_eq!(sqrt_fixed_points(9), vec![0, 1]);
+}
+
+#[test]
+fn test_residue_set() {
+    for p in [3, 7, 17, 97, 1009] {
+        let set = ResidueSet::new(p);
+        for n in 0..2 * p {
+            assert_eq!(set.contains(n), legendre_symbol(n, p) == 1);
+        }
+    }
+}
+
+#[test]
+fn test_roots_match() {
+    assert!(roots_match(2, 7, 3));
+    assert!(roots_match(2, 7, 4));
+    assert!(roots_match(9, 7, 3 + 7));
+    assert!(roots_match(2, 7, 4 + 3 * 7));
+    assert!(!roots_match(2, 7, 5));
+    assert!(!roots_match(3, 7, 3));
+
+    let p = 1000000007;
+    let n = 4;
+    let r = tonelli_shanks(n, p).unwrap();
+    assert!(roots_match(n, p, r));
+    assert!(roots_match(n + p, p, r + p));
+    assert!(roots_match(n, p, p - r + 5 * p));
+}
+
+#[test]
+fn test_sqrt_gaussian() {
+    let square = |(c, d): (u64, u64), p: u64| ((c * c + p * p - d * d) % p, (2 * c * d) % p);
+
+    for p in [3, 7, 11, 19, 23, 103] {
+        for a in 0..p {
+            for b in 0..p {
+                match sqrt_gaussian(a, b, p) {
+                    Some(root) => assert_eq!(square(root, p), (a, b)),
+                    None => {
+                        let norm = (a * a + b * b) % p;
+                        assert_eq!(legendre_symbol(norm, p), -1);
+                    }
+                }
+            }
+        }
+    }
+
+    assert_eq!(sqrt_gaussian(2, 1, 13), None);
+}
+
+#[test]
+fn test_modular_sqrt_opcount() {
+    // p = 7: Euler's criterion raises to 3 = 0b11, the root to 2 = 0b10
+    assert_eq!(
+        modular_sqrt_opcount(2, 7),
+        (
+            Some(3),
+            OpCount {
+                mults: 3,
+                sqrs: 4,
+                invs: 0
+            }
+        )
+    );
+
+    // p = 11: Euler's criterion raises to 5 = 0b101, the root to 3 = 0b11
+    assert_eq!(
+        modular_sqrt_opcount(3, 11),
+        (
+            tonelli_shanks(3, 11),
+            OpCount {
+                mults: 4,
+                sqrs: 5,
+                invs: 0
+            }
+        )
+    );
+
+    // A non-residue only pays for the Legendre symbol
+    assert_eq!(
+        modular_sqrt_opcount(3, 7),
+        (
+            None,
+            OpCount {
+                mults: 2,
+                sqrs: 2,
+                invs: 0
+            }
+        )
+    );
+
+    assert_eq!(modular_sqrt_opcount(0, 7), (Some(0), OpCount::default()));
+
+    for n in 1..97 {
+        let (root, ops) = modular_sqrt_opcount(n, 97);
+        assert_eq!(root, tonelli_shanks(n, 97));
+        assert_eq!(ops.invs, 0);
+    }
+}
+
+#[test]
+fn test_legendre_symbol_product() {
+    assert_eq!(legendre_symbol_product(&[], 7), 1);
+    assert_eq!(legendre_symbol_product(&[3, 5], 7), 1);
+    assert_eq!(legendre_symbol_product(&[2, 3], 7), -1);
+    assert_eq!(legendre_symbol_product(&[2, 7, 3], 7), 0);
+
+    for p in [7, 11, 13, 17] {
+        for a in 0..p {
+            for b in 0..p {
+                for c in [1, 2, 3, p - 1] {
+                    let product = (a * b * c) % p;
+                    assert_eq!(
+                        legendre_symbol_product(&[a, b, c], p),
+                        legendre_symbol(product, p)
+                    );
+                }
+            }
+        }
+    }
+
+    let p = 1000000007;
+    let factors = [u64::MAX, u64::MAX - 1, 123456789, 987654321];
+    let expected: i32 = factors.iter().map(|&a| legendre_symbol(a, p)).product();
+    assert_eq!(legendre_symbol_product(&factors, p), expected);
+}
+
+#[test]
+fn test_explain_no_root() {
+    assert_eq!(
+        explain_no_root(3, 7).unwrap(),
+        "3 is a quadratic non-residue mod 7 because 3^3 ≡ 6 ≡ -1 (mod 7)"
+    );
+    assert_eq!(
+        explain_no_root(3, 17).unwrap(),
+        "3 is a quadratic non-residue mod 17 because 3^8 ≡ 16 ≡ -1 (mod 17)"
+    );
+
+    assert_eq!(explain_no_root(2, 7), None);
+    assert_eq!(explain_no_root(0, 7), None);
+    assert_eq!(explain_no_root(1, 2), None);
+
+    for n in 0..17 {
+        assert_eq!(
+            explain_no_root(n, 17).is_some(),
+            tonelli_shanks(n, 17).is_none()
+        );
+    }
+}
+
+#[test]
+fn test_cipolla() {
+    for p in [3, 5, 7, 13, 17, 97, 257, 65537] {
+        for n in 0..p.min(500) {
+            match cipolla(n, p) {
+                Some(r) => assert_eq!((r * r) % p, n),
+                None => assert_eq!(legendre_symbol(n, p), -1),
+            }
+        }
+    }
+
+    assert_eq!(cipolla(1, 2), Some(1));
+    assert_eq!(cipolla(0, 7), Some(0));
+}
+
+#[test]
+fn test_sqrt_kunerth() {
+    // p ≡ 3 (mod 4) and p ≡ 1 (mod 4), up to primes beyond 2^32
+    for p in [
+        7,
+        23,
+        103,
+        65519,
+        2_305_843_009_213_693_951,
+        13,
+        17,
+        97,
+        65537,
+        998_244_353,
+        18_446_744_073_709_551_557,
+    ] {
+        for n in (0..300).chain((1..p.min(300)).map(|k| p - k)) {
+            assert_eq!(sqrt_kunerth(n, p), modular_sqrt(n, p), "n = {n}, p = {p}");
+        }
+
+        // Squares of known roots, which the search finds directly once r² < 64·p
+        for r in [2u64, 1000, 65535, 1 << 31] {
+            let n = ((r as u128 * r as u128) % p as u128) as u64;
+            let root = sqrt_kunerth(n, p).unwrap();
+            assert_eq!(root, modular_sqrt(n, p).unwrap());
+            assert_eq!(square_mod(root, p), n);
+        }
+    }
+
+    assert_eq!(sqrt_kunerth(1, 2), Some(1));
+    assert_eq!(sqrt_kunerth(2, 23), Some(5));
+    assert_eq!(sqrt_kunerth(5, 23), None);
+}
+
+#[test]
+fn test_tonelli_shanks_fallback() {
+    for p in [5, 13, 17, 97, 257, 65537] {
+        for n in 0..p.min(500) {
+            let (root, fallback) = tonelli_shanks_flagged(n, p);
+            assert_eq!(root, tonelli_shanks(n, p));
+            assert!(!fallback);
+        }
+    }
+
+    // 85 = 5 · 17 passes Euler's criterion for 16 but the main loop runs out
+    // of order, so the fallback factors the modulus and recovers a root
+    assert_eq!(legendre_symbol(16, 85), 1);
+    assert_eq!(tonelli_shanks_flagged(16, 85), (Some(4), true));
+    assert_eq!(tonelli_shanks_flagged(69, 85), (Some(18), true));
+    assert_eq!(tonelli_shanks_flagged(81, 205), (Some(9), true));
+    // 9 has no non-residue that passes Euler's criterion
+    assert_eq!(tonelli_shanks_flagged(1, 9), (Some(1), true));
+    assert_eq!(tonelli_shanks_certificate(16, 85), None);
+
+    // Every width falls back the same way
+    assert_eq!(tonelli_shanks_u32(16, 85), Some(4));
+    assert_eq!(tonelli_shanks_u128(16, 85), Some(4));
+}
+
+#[cfg(feature = "simd")]
+#[test]
+fn test_legendre_symbols_simd() {
+    let primes: Vec<u64> = (3..2000u64)
+        .filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0))
+        .chain([4294967291, 1000000007, 998244353])
+        .collect();
+
+    for a in [0, 1, 2, 3, 5, 10, 12345, 1000000007, u64::MAX] {
+        let expected: Vec<i32> = primes.iter().map(|&p| legendre_symbol(a, p)).collect();
+        assert_eq!(legendre_symbols_simd(a, &primes), expected);
+    }
+
+    assert_eq!(legendre_symbols_simd(2, &[]), Vec::<i32>::new());
+}
+
+#[test]
+fn test_square_roots_sum_invariant() {
+    let primes = [
+        3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89,
+        97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181,
+        191, 193, 197, 199, 257, 65537,
+    ];
+
+    for p in primes {
+        for n in 1..p.min(1000) {
+            if let Some((r1, r2)) = square_roots(n, p) {
+                assert_eq!(r1 + r2, p);
+                assert!(r1 < r2);
+            }
+        }
+        assert_eq!(square_roots(0, p), Some((0, 0)));
+        assert_eq!(square_roots(p, p), Some((0, 0)));
+    }
+
+    assert_eq!(square_roots(1, 2), Some((1, 1)));
+
+    let p = 1000000007;
+    let (r1, r2) = square_roots(4, p).unwrap();
+    assert_eq!(r1 + r2, p);
+}
+
+#[test]
+fn test_pow_mod_checked() {
+    assert_eq!(pow_mod_checked(2, 10, 1000), Ok(24));
+    assert_eq!(pow_mod_checked(3, 5, 7), Ok(5));
+    assert_eq!(pow_mod_checked(3, 5, 0), Err(TonelliError::ModulusZero));
+}
+
+#[test]
+fn test_legendre_symbol_checked() {
+    assert_eq!(legendre_symbol_checked(2, 7), Ok(1));
+    assert_eq!(legendre_symbol_checked(3, 7), Ok(-1));
+    assert_eq!(legendre_symbol_checked(7, 7), Ok(0));
+    assert_eq!(legendre_symbol_checked(1, 2), Ok(1));
+    assert_eq!(
+        legendre_symbol_checked(3, 0),
+        Err(TonelliError::ModulusZero)
+    );
+    assert_eq!(
+        legendre_symbol_checked(3, 10),
+        Err(TonelliError::ModulusEven)
+    );
+}
+
+#[test]
+fn test_compat_panics_on_zero_modulus() {
+    use std::panic::catch_unwind;
+
+    assert!(catch_unwind(|| compat::pow_mod(2, 3, 0)).is_err());
+    assert!(catch_unwind(|| compat::legendre_symbol(2, 0)).is_err());
+    assert!(catch_unwind(|| compat::tonelli_shanks(2, 0)).is_err());
+    assert!(catch_unwind(|| compat::square_roots(2, 0)).is_err());
+
+    assert_eq!(compat::pow_mod(3, 5, 7), 5);
+    assert_eq!(compat::legendre_symbol(3, 7), -1);
+    assert_eq!(compat::find_quadratic_non_residue(7), 3);
+    assert_eq!(compat::tonelli_shanks(2, 7), Some(3));
+    assert_eq!(compat::square_roots(2, 7), Some((3, 4)));
+}
+
+#[test]
+fn test_square_mod() {
+    assert_eq!(square_mod(4, 7), 2);
+    assert_eq!(square_mod(0, 7), 0);
+    assert_eq!(square_mod(10, 7), 2);
+    assert_eq!(square_mod(u64::MAX, 1000000007), 114944269);
+    assert_eq!(square_mod(u64::MAX, u64::MAX), 0);
+    assert_eq!(square_mod(u64::MAX - 1, u64::MAX), 1);
+}
+
+#[test]
+fn test_square_roots_exhaustive_small_primes() {
+    let primes = (2..200u64).filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0));
+
+    for p in primes {
+        for n in 0..p {
+            match square_roots(n, p) {
+                Some((r1, r2)) => {
+                    assert_eq!(square_mod(r1, p), n % p, "n = {n}, p = {p}");
+                    assert_eq!(square_mod(r2, p), n % p, "n = {n}, p = {p}");
+                }
+                None => assert_eq!(legendre_symbol(n, p), -1, "n = {n}, p = {p}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_compress_decompress_y() {
+    for p in [2, 3, 7, 17, 97, 257] {
+        for y in 0..p {
+            assert_eq!(decompress_y(square_mod(y, p), p, compress_y(y, p)), Some(y));
+        }
+    }
+
+    assert_eq!(decompress_y(0, 7, true), None);
+    assert_eq!(decompress_y(3, 7, false), None);
+
+    let p = 1000000007;
+    for y in [1, 2, 123456789, p - 1] {
+        assert_eq!(decompress_y(square_mod(y, p), p, compress_y(y, p)), Some(y));
+    }
+}
+
+#[test]
+fn test_legendre_symbol_multiples_of_p() {
+    for p in [3, 7, 17, 97, 1000000007] {
+        assert_eq!(legendre_symbol(p, p), 0);
+        assert_eq!(legendre_symbol(2 * p, p), 0);
+        assert_eq!(legendre_symbol(5 * p, p), 0);
+
+        for a in [p + 1, p + 2, 2 * p + 3, 10 * p - 1, u64::MAX] {
+            assert_eq!(legendre_symbol(a, p), legendre_symbol(a % p, p));
+        }
+    }
+}
+
+#[test]
+fn test_sqrt_mod_small_powers_of_two() {
+    let brute = |n: u64, m: u64| (0..m).filter(|x| (x * x) % m == n % m).collect::<Vec<_>>();
+
+    for n in 0..32 {
+        assert_eq!(sqrt_mod_2(n), brute(n, 2));
+        assert_eq!(sqrt_mod_4(n), brute(n, 4));
+        assert_eq!(sqrt_mod_8(n), brute(n, 8));
+    }
+
+    assert_eq!(sqrt_mod_8(1), vec![1, 3, 5, 7]);
+    assert_eq!(sqrt_mod_8(u64::MAX), vec![]);
+}
+
+#[test]
+fn test_prime_field() {
+    for p in [2, 3, 5, 7, 13, 17, 97, 257, 65537, 998244353] {
+        let field = PrimeField::new(p);
+        assert_eq!(field.modulus(), p);
+        for n in 0..p.min(300) {
+            assert_eq!(field.sqrt(n), tonelli_shanks(n, p));
+        }
+    }
+}
+
+#[test]
+fn test_first_n_residues_with_roots() {
+    assert_eq!(first_n_residues_with_roots(7, 2), vec![(1, 1), (2, 3)]);
+    assert_eq!(
+        first_n_residues_with_roots(7, 10),
+        vec![(1, 1), (2, 3), (4, 2)]
+    );
+    assert_eq!(first_n_residues_with_roots(7, 0), vec![]);
+
+    for p in [17, 97, 1009] {
+        let residues = first_n_residues_with_roots(p, 20);
+        assert_eq!(residues.len(), 20.min((p as usize - 1) / 2));
+        for (n, r) in residues {
+            assert_eq!(legendre_symbol(n, p), 1);
+            assert_eq!(square_mod(r, p), n);
+        }
+    }
+}
+
+#[test]
+fn test_sqrt_iter_ext() {
+    let roots: Vec<u64> = (1..101).sqrt_mod(101).flatten().collect();
+    assert_eq!(roots.len(), 50);
+    for r in &roots {
+        assert!(legendre_symbol(square_mod(*r, 101), 101) == 1);
+    }
+
+    let roots: Vec<(u64, u64)> = (1..100u64)
+        .filter(|n| n % 3 == 0)
+        .map(|n| n * 7)
+        .sqrt_mod(97)
+        .zip((1..100u64).filter(|n| n % 3 == 0).map(|n| n * 7))
+        .filter_map(|(r, n)| r.map(|r| (n, r)))
+        .collect();
+    assert!(!roots.is_empty());
+    for (n, r) in roots {
+        assert_eq!(Some(r), tonelli_shanks(n, 97));
+    }
+
+    let all: Vec<Option<u64>> = [2, 3, 4].into_iter().sqrt_mod(7).collect();
+    assert_eq!(all, vec![Some(3), None, Some(2)]);
+}
+
+#[test]
+fn test_prime_field_shared_between_threads() {
+    use std::sync::Arc;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync + Clone>() {}
+    assert_send_sync::<PrimeField>();
+
+    let p = 998244353;
+    let field = Arc::new(PrimeField::new(p));
+    let handles: Vec<_> = (0..8u64)
+        .map(|t| {
+            let field = Arc::clone(&field);
+            thread::spawn(move || {
+                (t * 1000..(t + 1) * 1000)
+                    .map(|n| (n, field.sqrt(n)))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        for (n, root) in handle.join().unwrap() {
+            assert_eq!(root, tonelli_shanks(n, p));
+        }
+    }
+
+    let cloned = (*field).clone();
+    assert_eq!(cloned, *field);
+}
+
+#[test]
+fn test_calibrate_threshold() {
+    let threshold = calibrate_threshold();
+    assert!((2..=32).contains(&threshold));
+    assert_eq!(calibrate_threshold(), threshold);
+    assert!((2..=32).contains(&DEFAULT_CIPOLLA_THRESHOLD));
+}
+
+#[test]
+fn test_modular_sqrt() {
+    let primes = [
+        2, 3, 5, 7, 13, 17, 97, 257, 65537, 7340033, 998244353, 2013265921, 3221225473,
+    ];
+    for p in primes {
+        for n in 0..300 {
+            assert_eq!(modular_sqrt(n, p), tonelli_shanks(n, p));
+        }
+    }
+
+    // The result does not depend on whether the default or the calibrated
+    // threshold is in effect
+    let threshold = calibrate_threshold();
+    for n in 0..300 {
+        assert_eq!(modular_sqrt(n, 3221225473), tonelli_shanks(n, 3221225473));
+        assert_eq!(modular_sqrt(n, 2013265921), tonelli_shanks(n, 2013265921));
+    }
+    assert_eq!(calibrate_threshold(), threshold);
+}
+
+#[test]
+fn test_roots_verified() {
+    assert_eq!(roots_verified(2, 7), vec![(3, true), (4, true)]);
+    assert_eq!(roots_verified(0, 7), vec![(0, true)]);
+    assert_eq!(roots_verified(1, 2), vec![(1, true)]);
+    assert_eq!(roots_verified(3, 7), vec![]);
+
+    for p in [3, 5, 7, 11, 13, 17, 97, 257, 65537, 998244353] {
+        for n in 0..p.min(300) {
+            let roots = roots_verified(n, p);
+            assert!(roots.iter().all(|&(_, ok)| ok));
+            let expected = match legendre_symbol(n, p) {
+                1 => 2,
+                0 => 1,
+                _ => 0,
+            };
+            assert_eq!(roots.len(), expected);
+        }
+    }
+}
+
+#[test]
+fn test_neg_mod() {
+    assert_eq!(neg_mod(3, 7), 4);
+    assert_eq!(neg_mod(0, 7), 0);
+    assert_eq!(neg_mod(10, 7), 4);
+    assert_eq!(neg_mod(u64::MAX, u64::MAX - 1), u64::MAX - 2);
+}
+
+#[test]
+fn test_mod_inverse() {
+    assert_eq!(mod_inverse(3, 7), Some(5));
+    assert_eq!(mod_inverse(1, 7), Some(1));
+    assert_eq!(mod_inverse(0, 7), None);
+    assert_eq!(mod_inverse(2, 4), None);
+    assert_eq!(mod_inverse(3, 10), Some(7));
+    assert_eq!(mod_inverse(5, 1), Some(0));
+
+    let p = u64::MAX - 58; // largest prime below 2^64
+    let x = 123456789;
+    let inverse = mod_inverse(x, p).unwrap();
+    assert_eq!((x as u128 * inverse as u128) % p as u128, 1);
+}
+
+#[test]
+fn test_sqrt_with_inverses() {
+    assert_eq!(sqrt_with_inverses(2, 7), Some((3, 4, 5)));
+    assert_eq!(sqrt_with_inverses(3, 7), None);
+    assert_eq!(sqrt_with_inverses(0, 7), None);
+
+    for p in [3, 13, 17, 97, 65537, 998244353] {
+        for n in 1..p.min(300) {
+            if let Some((y, neg, inverse)) = sqrt_with_inverses(n, p) {
+                assert_eq!(square_mod(y, p), n);
+                assert_eq!((y + neg) % p, 0);
+                assert_eq!((y as u128 * inverse as u128) % p as u128, 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_next_non_residue() {
+    // Non-residues mod 17 are 3, 5, 6, 7, 10, 11, 12, 14
+    assert_eq!(next_non_residue(0, 17), Some(3));
+    assert_eq!(next_non_residue(3, 17), Some(3));
+    assert_eq!(next_non_residue(4, 17), Some(5));
+    assert_eq!(next_non_residue(8, 17), Some(10));
+    assert_eq!(next_non_residue(13, 17), Some(14));
+    assert_eq!(next_non_residue(15, 17), Some(3));
+    assert_eq!(next_non_residue(17 + 8, 17), Some(10));
+
+    assert_eq!(next_non_residue(2, 7), Some(3));
+    assert_eq!(next_non_residue(0, 2), None);
+
+    for p in [7, 11, 13, 97] {
+        assert_eq!(next_non_residue(2, p), Some(find_quadratic_non_residue(p)));
+    }
+}
+
+#[cfg(feature = "bench-api")]
+#[test]
+fn test_bench_report() {
+    for p in [7, 17, 998244353] {
+        let report = bench_report(p, 200);
+        assert!(report.u64_ns_per_op > 0.0);
+        assert!(report.u128_ns_per_op > 0.0);
+    }
+
+    let report = bench_report(17, 0);
+    assert!(report.u64_ns_per_op > 0.0);
+}
+
+#[test]
+fn test_square_roots_into() {
+    let mut out = [99; 2];
+    assert_eq!(square_roots_into(3, 7, &mut out), 0);
+    assert_eq!(out, [99, 99]);
+
+    assert_eq!(square_roots_into(0, 7, &mut out), 1);
+    assert_eq!(out, [0, 99]);
+
+    assert_eq!(square_roots_into(1, 2, &mut out), 1);
+    assert_eq!(out, [1, 99]);
+
+    assert_eq!(square_roots_into(2, 7, &mut out), 2);
+    assert_eq!(out, [3, 4]);
+
+    assert_eq!(square_roots_into(2, 17, &mut out), 2);
+    assert_eq!(out, [6, 11]);
+}
+
+#[test]
+fn test_residue_density() {
+    for p in [3, 7, 17, 97, 1009] {
+        let expected = ((p - 1) / 2) as f64 / p as f64;
+        assert!((residue_density(p, 0, p) - expected).abs() < 1e-12);
+        assert!((residue_density(p, 5 * p + 3, 6 * p + 3) - expected).abs() < 1e-12);
+        assert!((residue_density(p, 0, 10 * p) - expected).abs() < 1e-12);
+    }
+
+    assert_eq!(residue_density(7, 3, 4), 0.0);
+    assert_eq!(residue_density(7, 4, 5), 1.0);
+    assert_eq!(residue_density(7, 5, 5), 0.0);
+    assert_eq!(residue_density(7, 6, 2), 0.0);
+}
+
+sqrt_tests! {
+    sqrt_invariants_7: 7,
+    sqrt_invariants_17: 17,
+    sqrt_invariants_97: 97,
+    sqrt_invariants_1000003: 1000003,
+}
+
+#[test]
+fn test_sqrt_tower_depth() {
+    for p in [3u64, 5, 7, 13, 17, 41, 97, 257] {
+        let s = (p - 1).trailing_zeros();
+
+        // powers[k] is the set of 2^k-th powers of nonzero elements
+        let mut powers = vec![(1..p).collect::<Vec<u64>>()];
+        for _ in 0..=s {
+            let mut next: Vec<u64> = powers.last().unwrap().iter().map(|x| x * x % p).collect();
+            next.sort();
+            next.dedup();
+            powers.push(next);
+        }
+
+        for n in 1..p {
+            let depth = (0..powers.len())
+                .rev()
+                .find(|&k| powers[k].contains(&n))
+                .unwrap() as u32;
+            let expected = if depth as usize == powers.len() - 1 {
+                u32::MAX
+            } else {
+                depth
+            };
+            assert_eq!(sqrt_tower_depth(n, p), expected, "n = {n}, p = {p}");
+        }
+
+        assert_eq!(sqrt_tower_depth(0, p), u32::MAX);
+    }
+
+    assert_eq!(sqrt_tower_depth(1, 2), u32::MAX);
+}
+
+#[test]
+#[should_panic]
+fn test_sqrt_tower_depth_rejects_even_modulus() {
+    sqrt_tower_depth(2, 4);
+}
+
+#[test]
+#[should_panic]
+fn test_sqrt_tower_depth_rejects_composite() {
+    sqrt_tower_depth(3, 15); // 3^7 ≡ 12 (mod 15) never squares to 1
+}
+
+#[test]
+fn test_residues_iter() {
+    for p in [3, 5, 7, 13, 17, 97, 1009] {
+        let mut residues: Vec<u64> = residues_iter(p).map(|(n, _)| n).collect();
+        residues.sort();
+        let expected: Vec<u64> = (1..p).filter(|&n| legendre_symbol(n, p) == 1).collect();
+        assert_eq!(residues, expected);
+
+        for (n, r) in residues_iter(p) {
+            assert_eq!(tonelli_shanks(n, p), Some(r));
+        }
+    }
+
+    assert_eq!(residues_iter(2).collect::<Vec<_>>(), vec![(1, 1)]);
+}
+
+#[test]
+fn test_roots_of_class() {
+    for p in [3, 5, 7, 13, 17, 97, 1009] {
+        for reference in 0..2 * p {
+            let class = roots_of_class(reference, p);
+            match legendre_symbol(reference, p) {
+                1 => {
+                    assert_eq!(class.len() as u64, (p - 1) / 2);
+                    for &(n, r) in &class {
+                        assert_eq!(legendre_symbol(n, p), legendre_symbol(reference, p));
+                        assert_eq!(tonelli_shanks(n, p), Some(r));
+                    }
+                }
+                _ => assert!(class.is_empty()),
+            }
+        }
+    }
+
+    assert_eq!(roots_of_class(1, 2), vec![(1, 1)]);
+}
+
+#[test]
+fn test_curve_rhs() {
+    assert_eq!(curve_rhs(3, 2, 3, 97), 36);
+    assert_eq!(curve_rhs(0, 2, 3, 97), 3);
+    assert_eq!(curve_rhs(3 + 97, 2 + 97, 3 + 97, 97), 36);
+
+    let p = u64::MAX - 58;
+    let x = p - 1;
+    // (-1)³ + a·(-1) + b = b - a - 1
+    assert_eq!(curve_rhs(x, 5, 10, p), 4);
+}
+
+#[test]
+fn test_is_on_curve() {
+    let (a, b, p) = (2, 3, 97);
+    assert!(is_on_curve(3, 6, a, b, p));
+    assert!(is_on_curve(3, 91, a, b, p));
+    assert!(!is_on_curve(3, 7, a, b, p));
+    assert!(!is_on_curve(1, 1, a, b, p));
+
+    let mut on_curve = 0;
+    for x in 0..p {
+        for y in 0..p {
+            if is_on_curve(x, y, a, b, p) {
+                on_curve += 1;
+                assert_eq!((y * y) % p, (x * x * x + a * x + b) % p);
+            }
+        }
+    }
+    assert!(on_curve > 0);
+}
+
+#[test]
+fn test_decompress_point() {
+    let (a, b, p) = (2, 3, 97);
+    assert_eq!(decompress_point(3, a, b, p), Some((6, 91)));
+
+    for x in 0..p {
+        match decompress_point(x, a, b, p) {
+            Some((y1, y2)) => {
+                assert!(is_on_curve(x, y1, a, b, p));
+                assert!(is_on_curve(x, y2, a, b, p));
+            }
+            None => assert!((0..p).all(|y| !is_on_curve(x, y, a, b, p))),
+        }
+    }
+}
+
+#[test]
+fn test_decompress_or_increment() {
+    assert_eq!(decompress_or_increment(3, 2, 3, 97), (3, 6, 91));
+    assert_eq!(decompress_or_increment(5, 2, 3, 97), (10, 21, 76));
+
+    for (a, b, p) in [(2, 3, 97), (0, 7, 10009), (5, 10, 65537)] {
+        for x in (0..100).chain([p - 2, p - 1, p, u64::MAX]) {
+            let (x2, y1, y2) = decompress_or_increment(x, a, b, p);
+            assert!(x2 < p);
+            assert!(is_on_curve(x2, y1, a, b, p));
+            assert!(is_on_curve(x2, y2, a, b, p));
+            assert_eq!(decompress_point(x2, a, b, p), Some((y1, y2)));
+
+            // Every x-coordinate skipped over has no point
+            let mut skipped = x % p;
+            while skipped != x2 {
+                assert_eq!(decompress_point(skipped, a, b, p), None);
+                skipped = (skipped + 1) % p;
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_decompress_or_increment_no_points() {
+    // x³ + 2x + 2 ≡ 2 (mod 3) for every x, and 2 is not a square mod 3
+    decompress_or_increment(0, 2, 2, 3);
+}
+
+#[test]
+fn test_decompress_points() {
+    assert_eq!(decompress_points(&[], 2, 3, 97), vec![]);
+
+    for (a, b, p) in [(2, 3, 97), (0, 7, 10009), (5, 10, 65537), (1, 1, 2)] {
+        let xs: Vec<u64> = (0..200).rev().chain([p - 1, p, u64::MAX]).collect();
+        let expected: Vec<_> = xs.iter().map(|&x| decompress_point(x, a, b, p)).collect();
+        assert_eq!(decompress_points(&xs, a, b, p), expected);
+    }
+}
+
+#[test]
+fn test_crt() {
+    assert_eq!(crt(&[]), Some(0));
+    assert_eq!(crt(&[(3, 7)]), Some(3));
+    assert_eq!(crt(&[(10, 7)]), Some(3));
+    assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some(23));
+    assert_eq!(crt(&[(1, 4), (1, 6)]), None);
+    assert_eq!(crt(&[(1, u64::MAX), (1, 2)]), None);
+
+    let moduli = [4294967291, 4294967279];
+    let x = 12345678901234567;
+    let congruences: Vec<(u64, u64)> = moduli.iter().map(|&m| (x % m, m)).collect();
+    assert_eq!(crt(&congruences), Some(x));
+}
+
+#[test]
+fn test_sqrt_mod_primorial() {
+    assert_eq!(sqrt_mod_primorial(5, 1), Some(vec![0]));
+    assert_eq!(sqrt_mod_primorial(4, 5), Some(vec![2, 8, 22, 28]));
+
+    let m = 2 * 3 * 5 * 7 * 11 * 13;
+    for n in [0, 1, 4, 9, 10, 25, 49, 64, 100, 121, 169, 1000, 4096, 30029] {
+        let brute: Vec<u64> = (0..m).filter(|x| (x * x) % m == n % m).collect();
+        let roots = sqrt_mod_primorial(n, 13);
+        if brute.is_empty() {
+            assert_eq!(roots, None, "n = {n}");
+        } else {
+            assert_eq!(roots, Some(brute), "n = {n}");
+        }
+    }
+
+    let roots = sqrt_mod_primorial(4, 47).unwrap();
+    assert_eq!(roots.len(), 1 << 14);
+    let m = 614889782588491410u64;
+    for r in roots.iter().take(50) {
+        assert_eq!(square_mod(*r, m), 4);
+    }
+}
+
+#[test]
+fn test_modular_sqrt_warn() {
+    // 3221225473 = 3 · 2^30 + 1
+    let p = 3221225473;
+    for n in [2, 3, 4, 5] {
+        let (root, warning) = modular_sqrt_warn(n, p);
+        assert_eq!(root, tonelli_shanks(n, p));
+        assert_eq!(
+            warning.as_deref(),
+            Some("warning: v2(p-1) = 30 is large; consider Cipolla")
+        );
+    }
+
+    for p in [7, 11, 19, 1000003] {
+        for n in 0..20 {
+            assert_eq!(modular_sqrt_warn(n, p), (tonelli_shanks(n, p), None));
+        }
+    }
+
+    assert_eq!(modular_sqrt_warn(2, 17).1, None);
+    assert_eq!(
+        modular_sqrt_warn(2, 998244353).1.unwrap(),
+        "warning: v2(p-1) = 23 is large; consider Cipolla"
+    );
+    assert_eq!(modular_sqrt_warn(1, 2), (Some(1), None));
+}
+
+#[test]
+fn test_jacobi_symbols() {
+    let moduli: Vec<u64> = (1..200).step_by(2).collect();
+    for n in 0..60 {
+        let symbols = jacobi_symbols(n, &moduli);
+        assert_eq!(symbols.len(), moduli.len());
+        for (&m, &symbol) in moduli.iter().zip(&symbols) {
+            assert_eq!(symbol, jacobi_symbol(n, m));
+        }
+    }
+
+    // Agrees with the Legendre symbol on primes
+    for p in [3, 5, 7, 11, 13, 97, 1000003] {
+        for n in 0..50 {
+            assert_eq!(jacobi_symbol(n, p), legendre_symbol(n, p));
+        }
+    }
+
+    // gcd(n, m) > 1 gives 0
+    assert_eq!(jacobi_symbols(6, &[9, 15, 21, 35, 1]), vec![0, 0, 0, -1, 1]);
+    assert_eq!(jacobi_symbols(35, &[15, 21, 49, 11]), vec![0, 0, 0, -1]);
+    assert_eq!(jacobi_symbols(7, &[]), Vec::<i32>::new());
+}
+
+#[test]
+fn test_multiples_of_p() {
+    // Covers the p ≡ 3 (mod 4) fast path, the main loop and very large s
+    for p in [3, 5, 7, 13, 17, 97, 1000003, 998244353, 3221225473] {
+        for k in [0, 1, 2, 3, 10, 1000] {
+            let n = k * p;
+            assert_eq!(tonelli_shanks(n, p), Some(0));
+            assert_eq!(square_roots(n, p), Some((0, 0)));
+            assert_eq!(cipolla(n, p), Some(0));
+            assert_eq!(modular_sqrt(n, p), Some(0));
+            assert_eq!(PrimeField::new(p).sqrt(n), Some(0));
+        }
+
+        let n = u64::MAX - u64::MAX % p;
+        assert_eq!(tonelli_shanks(n, p), Some(0));
+        assert_eq!(square_roots(n, p), Some((0, 0)));
+    }
+
+    assert_eq!(tonelli_shanks(7, 7), Some(0));
+    assert_eq!(square_roots(7, 7), Some((0, 0)));
+    assert_eq!(tonelli_shanks(14, 7), Some(0));
+    assert_eq!(tonelli_shanks(4, 2), Some(0));
+}
+
+#[test]
+fn test_discrete_log_ph() {
+    // (p, primitive root, factorization of p - 1), all with smooth p - 1
+    let cases = [
+        (7, 3, &[(2, 1), (3, 1)][..]),
+        (97, 5, &[(2, 5), (3, 1)]),
+        (257, 3, &[(2, 8)]),
+        (7681, 17, &[(2, 9), (3, 1), (5, 1)]),
+        (65537, 3, &[(2, 16)]),
+        // Odd prime powers exercise the recursive split for q > 2
+        (39367, 3, &[(2, 1), (3, 9)]),
+        (62501, 2, &[(2, 2), (5, 6)]),
+        (86093443, 2, &[(2, 1), (3, 16)]),
+    ];
+
+    for (p, g, factors) in cases {
+        // Brute-force logarithms along the powers of g
+        let mut power = 1;
+        for x in 0..(p - 1).min(3000) {
+            assert_eq!(discrete_log_ph(g, power, p, factors), Some(x));
+            power = power * g % p;
+        }
+        for x in [(p - 1) / 3, p - 2, 1234567 % (p - 1)] {
+            assert_eq!(discrete_log_ph(g, pow_mod(g, x, p), p, factors), Some(x));
+        }
+        assert_eq!(discrete_log_ph(g, 0, p, factors), None);
+    }
+
+    // A base of smaller order only reaches its own subgroup
+    let p = 97;
+    for base in [4, 8, 35, 96] {
+        let powers: Vec<u64> = (0..96).map(|x| pow_mod(base, x, p)).collect();
+        for target in 1..p {
+            let smallest = powers.iter().position(|&y| y == target);
+            let x = discrete_log_ph(base, target, p, &[(2, 5), (3, 1)]);
+            assert_eq!(x, smallest.map(|x| x as u64));
+        }
+    }
+
+    assert_eq!(discrete_log_ph(3, 1, 7, &[]), Some(0));
+    assert_eq!(discrete_log_ph(3, 3, 7, &[]), None);
+}
+
+#[test]
+fn test_nth_power_of_two_root() {
+    for p in [3, 5, 7, 13, 17, 97, 257, 7681, 65537] {
+        let field = PrimeField::new(p);
+
+        // Entry j has order exactly 2^j
+        let tower = field.unity_tower();
+        assert_eq!(tower.len() as u32, (p - 1).trailing_zeros() + 1);
+        for (j, &root) in tower.iter().enumerate() {
+            assert_eq!(pow_mod(root, 1 << j, p), 1);
+            if j > 0 {
+                assert_eq!(pow_mod(root, 1 << (j - 1), p), p - 1);
+            }
+        }
+
+        for k in 0..20u32 {
+            let power = |x: u64| (0..k).fold(x, |y, _| y * y % p);
+            // Brute-force the set of 2^k-th powers on the smaller primes
+            let is_power: Vec<bool> = if p < 10000 {
+                let mut seen = vec![false; p as usize];
+                for x in 0..p {
+                    seen[power(x) as usize] = true;
+                }
+                seen
+            } else {
+                Vec::new()
+            };
+
+            for n in (0..p).take(2000) {
+                let root = field.nth_power_of_two_root(n, k);
+                if let Some(r) = root {
+                    assert_eq!(power(r), n, "p = {p}, k = {k}, n = {n}");
+                    if k > 0 {
+                        assert!(r <= p - r);
+                    }
+                }
+                if p < 10000 {
+                    assert_eq!(
+                        root.is_some(),
+                        is_power[n as usize],
+                        "p = {p}, k = {k}, n = {n}"
+                    );
+                }
+                if k == 1 {
+                    assert_eq!(root, field.sqrt(n));
+                }
+            }
+        }
+    }
+
+    // Repeated square roots land on a 2^k-th root after k steps
+    let field = PrimeField::new(65537);
+    let mut x: u64 = 3;
+    for k in 1..=16 {
+        x = x * x % 65537;
+        assert!(field.nth_power_of_two_root(x, k).is_some());
+    }
+}
+
+#[test]
+fn test_qr_subgroup_generator() {
+    let order = |g: u64, p: u64| {
+        let mut x = g;
+        let mut k = 1;
+        while x != 1 {
+            x = x * g % p;
+            k += 1;
+        }
+        k
+    };
+
+    for p in [5, 7, 11, 13, 17, 41, 97, 257, 1009, 7681] {
+        let g = primitive_root(p).unwrap();
+        assert_eq!(order(g, p), p - 1);
+        assert!((1..g).all(|h| order(h, p) < p - 1));
+
+        let h = qr_subgroup_generator(p).unwrap();
+        assert_eq!(legendre_symbol(h, p), 1);
+        assert_eq!(order(h, p), (p - 1) / 2);
+    }
+
+    assert_eq!(qr_subgroup_generator(2), None);
+    assert_eq!(qr_subgroup_generator(3), None);
+    assert_eq!(qr_subgroup_generator(0), None);
+
+    assert_eq!(factorize(7680), vec![(2, 9), (3, 1), (5, 1)]);
+    assert_eq!(factorize(1000002), vec![(2, 1), (3, 1), (166667, 1)]);
+    assert_eq!(factorize(4294967297), vec![(641, 1), (6700417, 1)]);
+    assert_eq!(factorize(0), vec![]);
+}
+
+#[test]
+fn test_is_primitive_root() {
+    let roots_7: Vec<u64> = (0..14).filter(|&g| is_primitive_root(g, 7)).collect();
+    assert_eq!(roots_7, vec![3, 5, 10, 12]);
+
+    let roots_11: Vec<u64> = (0..11).filter(|&g| is_primitive_root(g, 11)).collect();
+    assert_eq!(roots_11, vec![2, 6, 7, 8]);
+
+    // Agrees with a brute-force order computation
+    for p in [13, 17, 41, 97] {
+        for g in 1..p {
+            let order = (1..p).find(|&k| pow_mod(g, k, p) == 1).unwrap();
+            assert_eq!(is_primitive_root(g, p), order == p - 1);
+        }
+        assert!(is_primitive_root(primitive_root(p).unwrap(), p));
+    }
+
+    assert!(is_primitive_root(1, 2));
+    assert!(!is_primitive_root(0, 2));
+    assert!(!is_primitive_root(3, 1));
+}
+
+#[test]
+fn test_sqrt_canonical() {
+    for p in [3, 5, 7, 13, 17, 97, 65537, 1000003, 998244353] {
+        let field = PrimeField::new(p);
+        let inv_two = field.inv_two().unwrap();
+        assert_eq!(2 * inv_two % p, 1);
+
+        for n in 0..300 {
+            let root = field.sqrt_canonical(n);
+            assert_eq!(root, modular_sqrt(n, p));
+            assert_eq!(root, tonelli_shanks(n, p));
+            if let Some(r) = root {
+                assert!(r <= p - r);
+            }
+        }
+    }
+
+    let field = PrimeField::new(2);
+    assert_eq!(field.sqrt_canonical(0), Some(0));
+    assert_eq!(field.sqrt_canonical(1), Some(1));
+}
+
+#[test]
+fn test_tonelli_shanks_certificate() {
+    // p - 1 = 2^s · q with large s
+    for (p, s) in [
+        (65537, 16),
+        (7340033, 20),
+        (998244353, 23),
+        (3221225473u64, 30),
+    ] {
+        let mut longest = 0;
+        for n in 1..200 {
+            let certificate = tonelli_shanks_certificate(n, p);
+            assert_eq!(certificate.is_some(), tonelli_shanks(n, p).is_some());
+
+            if let Some(ms) = certificate {
+                assert_eq!(ms[0], s);
+                assert!(ms.windows(2).all(|w| w[0] > w[1]), "{ms:?}");
+                assert!(ms.len() as u32 <= s + 1);
+                longest = longest.max(ms.len());
+            }
+        }
+        assert!(longest > 2);
+    }
+
+    assert_eq!(tonelli_shanks_certificate(0, 17), Some(vec![]));
+    assert_eq!(tonelli_shanks_certificate(1, 17), Some(vec![4]));
+    assert_eq!(tonelli_shanks_certificate(1, 2), Some(vec![]));
+}
+
+#[test]
+fn test_square_root_max_min() {
+    assert_eq!(square_root_max(2, 7), Some(4));
+    assert_eq!(square_root_min(2, 7), Some(3));
+    assert_eq!(square_root_max(3, 7), None);
+    assert_eq!(square_root_min(3, 7), None);
+
+    for p in [3, 13, 17, 97, 1000003] {
+        for n in 0..100 {
+            let (min, max) = (square_root_min(n, p), square_root_max(n, p));
+            assert_eq!(min, tonelli_shanks(n, p));
+            assert_eq!(min.zip(max), square_roots(n, p));
+            if let (Some(min), Some(max)) = (min, max) {
+                assert!(min <= p / 2 && (min == 0 || min + max == p));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_modular_sqrt_mersenne() {
+    let p = (1u64 << 31) - 1;
+    for n in (0..2000).chain(p - 1000..p + 1000) {
+        assert_eq!(modular_sqrt_mersenne(n, 31), tonelli_shanks(n, p));
+    }
+
+    for exp in [2, 3, 5, 7, 13, 17, 19] {
+        let p = (1u64 << exp) - 1;
+        for n in 0..p.min(5000) {
+            assert_eq!(modular_sqrt_mersenne(n, exp), tonelli_shanks(n, p));
+        }
+    }
+
+    let p = (1u64 << 61) - 1;
+    for n in [0, 1, 2, 3, 4, 5, 12345, p - 1, p - 2, u64::MAX] {
+        let expected = tonelli_shanks_u128(n as u128, p as u128).map(|r| r as u64);
+        assert_eq!(modular_sqrt_mersenne(n, 61), expected);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_modular_sqrt_mersenne_rejects_composite() {
+    modular_sqrt_mersenne(2, 11); // 2047 = 23 · 89
+}
+
+#[test]
+fn test_sqrt_across_fields() {
+    let primes = [1000003, 2, 17, 7, 998244353, 97, 65537];
+    let fields: Vec<PrimeField> = primes.iter().map(|&p| PrimeField::new(p)).collect();
+
+    for n in 0..200 {
+        let roots = sqrt_across_fields(n, &fields);
+        assert_eq!(roots.len(), fields.len());
+        for ((field, &p), root) in fields.iter().zip(&primes).zip(roots) {
+            assert_eq!(field.modulus(), p);
+            assert_eq!(root, field.sqrt(n));
+            assert_eq!(root, tonelli_shanks(n, p));
+        }
+    }
+
+    assert_eq!(sqrt_across_fields(4, &[]), vec![]);
+}
+
+#[test]
+fn test_sqrt_mod_squarefree() {
+    let m = 3 * 5 * 7;
+    for n in 0..2 * m {
+        let brute: Vec<u64> = (0..m).filter(|&x| x * x % m == n % m).collect();
+        let roots = sqrt_mod_squarefree(n, m, &[3, 5, 7]);
+        assert_eq!(roots.clone().unwrap_or_default(), brute, "n = {n}");
+        assert_eq!(roots.is_some(), !brute.is_empty());
+        assert_eq!(sqrt_mod_squarefree(n, m, &[7, 3, 5]), roots);
+    }
+
+    // Coprime residues have 2^3 roots
+    assert_eq!(sqrt_mod_squarefree(1, m, &[3, 5, 7]).unwrap().len(), 8);
+    assert_eq!(sqrt_mod_squarefree(4, 2 * 13, &[2, 13]), Some(vec![2, 24]));
+    assert_eq!(
+        sqrt_mod_squarefree(4, 1000003 * 998244353, &[1000003, 998244353]),
+        Some(all_square_roots(4, 1000003 * 998244353))
+    );
+}
+
+#[test]
+fn test_sqrt_mod_squarefree_rejects_repeated_factors() {
+    assert_eq!(sqrt_mod_squarefree(4, 49, &[7, 7]), None);
+    assert_eq!(sqrt_mod_squarefree(4, 441, &[3, 7, 21]), None);
+}
+
+#[test]
+#[should_panic]
+#[cfg(debug_assertions)]
+fn test_sqrt_mod_squarefree_checks_factors() {
+    sqrt_mod_squarefree(4, 105, &[3, 5]);
+}
+
+#[test]
+fn test_cached_prime_field() {
+    for p in [2, 7, 17, 97, 65537, 1000003, 998244353] {
+        let mut cached = CachedPrimeField::new(PrimeField::new(p));
+        let uncached = PrimeField::new(p);
+
+        // Repeats, collisions on the same slot and values above p
+        let queries = (0..100)
+            .chain((0..100).rev())
+            .chain((0..20).map(|k| 5 + k * LEGENDRE_CACHE_SIZE as u64))
+            .chain((0..20).map(|k| p + k));
+        for n in queries {
+            assert_eq!(cached.sqrt(n), uncached.sqrt(n), "n = {n}, p = {p}");
+        }
+        assert_eq!(*cached.field(), uncached);
+    }
+}
+
+#[test]
+fn test_would_overflow_u64() {
+    assert!(would_overflow_u64(1 << 33, 1 << 33));
+    assert!(would_overflow_u64(1 << 32, 1 << 32));
+    assert!(would_overflow_u64(u64::MAX, 2));
+    assert!(!would_overflow_u64(u64::MAX, 1));
+    assert!(!would_overflow_u64(u64::MAX, 0));
+    assert!(!would_overflow_u64((1 << 32) - 1, (1 << 32) - 1));
+    assert!(!would_overflow_u64(1000003, 998244353));
+
+    // Exactly the primes above 2^32 can overflow a product of residues
+    for p in [65537, 1000003, 998244353, 4294967291] {
+        assert!(!would_overflow_u64(p - 1, p - 1));
+    }
+    for p in [4294967311, 1099511627791, 18446744073709551557] {
+        assert!(would_overflow_u64(p - 1, p - 1));
+    }
+}
+
+#[test]
+fn test_root_order() {
+    assert_eq!(RootOrder::default(), RootOrder::Ascending);
+
+    for m in [7, 8, 45, 105, 360, 1001, 1000003] {
+        for n in 0..50 {
+            let ascending = all_square_roots_ordered(n, m, RootOrder::Ascending);
+            let descending = all_square_roots_ordered(n, m, RootOrder::Descending);
+            let mut unsorted = all_square_roots_ordered(n, m, RootOrder::Unsorted);
+
+            assert_eq!(ascending, all_square_roots(n, m));
+            assert!(ascending.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(
+                descending.iter().rev().copied().collect::<Vec<_>>(),
+                ascending
+            );
+            assert_eq!(
+                modular_sqrt_any_ordered(n, m, RootOrder::Unsorted),
+                unsorted.first().copied()
+            );
+            unsorted.sort_unstable();
+            assert_eq!(unsorted, ascending);
+
+            assert_eq!(
+                modular_sqrt_any_ordered(n, m, RootOrder::Ascending),
+                ascending.first().copied()
+            );
+            assert_eq!(
+                modular_sqrt_any_ordered(n, m, RootOrder::Descending),
+                descending.first().copied()
+            );
+            assert_eq!(modular_sqrt_any(n, m), ascending.first().copied());
+        }
+    }
+}
+
+#[test]
+fn test_crt_check() {
+    assert_eq!(crt_check(&[(3, 7), (6, 17)]), Some(108));
+    assert_eq!(108 * 108 % 119, 2);
+
+    // Every choice of per-prime root gives one of the roots modulo 7 · 17
+    let mut combined: Vec<u64> = [3, 4]
+        .iter()
+        .flat_map(|&r| [6, 11].map(|s| crt_check(&[(r, 7), (s, 17)]).unwrap()))
+        .collect();
+    combined.sort_unstable();
+    assert_eq!(combined, all_square_roots(2, 119));
+
+    // Unreduced roots are accepted
+    assert_eq!(crt_check(&[(10, 7), (23, 17)]), Some(108));
+    assert_eq!(crt_check(&[(3, 7), (4, 7)]), None);
+    assert_eq!(crt_check(&[]), Some(0));
+}
+
+#[test]
+fn test_sqrt_graph_edges() {
+    for p in [2, 3, 7, 13, 17, 97, 1009] {
+        let edges = sqrt_graph_edges(p);
+        assert_eq!(edges.len() as u64, p);
+
+        for (i, &(x, y)) in edges.iter().enumerate() {
+            assert_eq!(x, i as u64);
+            assert_eq!(y, x * x % p);
+        }
+
+        // Walking p steps from any node must revisit a node
+        for start in 0..p {
+            let mut seen = vec![false; p as usize];
+            let mut node = start;
+            while !seen[node as usize] {
+                seen[node as usize] = true;
+                node = edges[node as usize].1;
+            }
+        }
+
+        // In-degree is 2 for nonzero residues and 0 for non-residues
+        let mut in_degree = vec![0; p as usize];
+        for &(_, y) in &edges {
+            in_degree[y as usize] += 1;
+        }
+        for n in 1..p {
+            let expected = if p == 2 {
+                1
+            } else if legendre_symbol(n, p) == 1 {
+                2
+            } else {
+                0
+            };
+            assert_eq!(in_degree[n as usize], expected);
+        }
+    }
+}
+
+#[test]
+fn test_modular_sqrt_nr_search_cost() {
+    // Fast path primes never search
+    for p in [3, 7, 11, 19, 1000003] {
+        for n in 0..50 {
+            assert_eq!(modular_sqrt_nr_search_cost(n, p), (tonelli_shanks(n, p), 0));
+        }
+    }
+
+    for p in [5, 13, 17, 41, 73, 97, 65537, 998244353] {
+        let z = find_quadratic_non_residue(p);
+        for n in 1..50 {
+            let (root, cost) = modular_sqrt_nr_search_cost(n, p);
+            assert_eq!(root, tonelli_shanks(n, p));
+            if root.is_some() && n % p != 0 {
+                assert!(cost > 0);
+                assert_eq!(cost as u64, z - 1);
+            } else {
+                assert_eq!(cost, 0);
+            }
+        }
+    }
+
+    // 2 is a non-residue mod 5, but 41 has least non-residue 3
+    assert_eq!(modular_sqrt_nr_search_cost(4, 5).1, 1);
+    assert_eq!(modular_sqrt_nr_search_cost(4, 41).1, 2);
+}
+
+#[test]
+fn test_values_with_single_root() {
+    for p in [3, 5, 7, 13, 17, 97, 1009] {
+        assert_eq!(values_with_single_root(p), vec![0]);
+    }
+    assert_eq!(values_with_single_root(2), vec![0, 1]);
+    assert_eq!(values_with_single_root(1), vec![0]);
+    assert_eq!(values_with_single_root(4), vec![]);
+    assert_eq!(values_with_single_root(9), vec![]);
+
+    for m in [4, 6, 8, 9, 10, 12, 15, 18, 25, 30, 36, 45, 100, 210] {
+        let counts: Vec<usize> = (0..m)
+            .map(|n| (0..m).filter(|&x| x * x % m == n).count())
+            .collect();
+        for n in 0..m {
+            assert_eq!(num_square_roots_composite(n, m), counts[n as usize]);
+            assert_eq!(num_square_roots_composite(n + m, m), counts[n as usize]);
+        }
+
+        let single: Vec<u64> = (0..m).filter(|&n| counts[n as usize] == 1).collect();
+        assert_eq!(values_with_single_root(m), single, "m = {m}");
+    }
+}
+
+#[test]
+fn test_is_sqrt_bijective() {
+    for p in [3, 7, 11, 19, 23, 43, 1000003] {
+        assert!(is_sqrt_bijective(p));
+    }
+    for p in [5, 13, 17, 41, 97, 65537] {
+        assert!(!is_sqrt_bijective(p));
+        assert_eq!(principal_sqrt_p3mod4(4, p), None);
+    }
+
+    // Squaring the residues is a permutation exactly when p ≡ 3 (mod 4)
+    for p in [5, 7, 11, 13, 17, 19, 23, 29, 97, 103] {
+        let residues: Vec<u64> = (1..p).filter(|&n| legendre_symbol(n, p) == 1).collect();
+        let mut squared: Vec<u64> = residues.iter().map(|&n| n * n % p).collect();
+        squared.sort_unstable();
+        squared.dedup();
+        assert_eq!(squared == residues, is_sqrt_bijective(p), "p = {p}");
+
+        if is_sqrt_bijective(p) {
+            for n in 0..p {
+                let root = principal_sqrt_p3mod4(n, p);
+                assert_eq!(root.map(|r| r.min(p - r)), tonelli_shanks(n, p));
+                if let Some(r) = root
+                    && n != 0
+                {
+                    assert_eq!(legendre_symbol(r, p), 1);
+                    assert_eq!(legendre_symbol(p - r, p), -1);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_modular_sqrt_rational_repr() {
+    let p = 1000003;
+    for (x, y) in [(3, 7), (1, 2), (5, 12), (22, 7), (355, 113), (1, 700)] {
+        let (a, b) = (x * x, y * y);
+        assert_eq!(modular_sqrt_rational_repr(a, b, p), Some((x, y)));
+        assert_eq!(modular_sqrt_rational_repr(a * 3, b * 3, p), Some((x, y)));
+    }
+
+    // (num, den) always describes a root, unless it fell back to (r, 1)
+    for p in [7, 17, 97, 65537, 1000003] {
+        for a in 0..40 {
+            for b in (1..10).filter(|b| b % p != 0) {
+                let n = a * mod_inverse(b, p).unwrap() % p;
+                let result = modular_sqrt_rational_repr(a, b, p);
+                assert_eq!(result.is_some(), tonelli_shanks(n, p).is_some());
+                if let Some((num, den)) = result {
+                    let root = num * mod_inverse(den, p).unwrap() % p;
+                    assert_eq!(root * root % p, n);
+                }
+            }
+        }
+    }
+
+    assert_eq!(modular_sqrt_rational_repr(0, 5, 17), Some((0, 1)));
+    assert_eq!(modular_sqrt_rational_repr(4, 17, 17), None);
+    assert_eq!(modular_sqrt_rational_repr(3, 1, 7), None);
+}
+
+#[test]
+fn test_minimal_polynomial_str() {
+    // Squarefree
+    assert_eq!(minimal_polynomial_str(2), "x^2 - 2");
+    assert_eq!(minimal_polynomial_str(30), "x^2 - 30");
+    assert_eq!(minimal_polynomial_str(1000003), "x^2 - 1000003");
+
+    // Square factors
+    assert_eq!(minimal_polynomial_str(8), "x^2 - 8 (= 2·sqrt(2))");
+    assert_eq!(minimal_polynomial_str(12), "x^2 - 12 (= 2·sqrt(3))");
+    assert_eq!(minimal_polynomial_str(72), "x^2 - 72 (= 6·sqrt(2))");
+    assert_eq!(
+        minimal_polynomial_str(1 << 63),
+        "x^2 - 9223372036854775808 (= 2147483648·sqrt(2))"
+    );
+
+    // Perfect squares
+    assert_eq!(minimal_polynomial_str(0), "x");
+    assert_eq!(minimal_polynomial_str(1), "x - 1");
+    assert_eq!(minimal_polynomial_str(144), "x - 12");
+    assert_eq!(minimal_polynomial_str(4294967296), "x - 65536");
+}
+
+#[test]
+fn test_modular_sqrt_is_independent_of_non_residue() {
+    // Tonelli-Shanks started from an arbitrary non-residue z, without
+    // normalizing the root
+    let tonelli_shanks_from = |n: u64, p: u64, z: u64| {
+        let s = (p - 1).trailing_zeros();
+        let q = (p - 1) >> s;
+        let mut c = pow_mod_u64(z, q, p);
+        let mut r = pow_mod_u64(n, q.div_ceil(2), p);
+        let mut t = pow_mod_u64(n, q, p);
+        let mut m = s;
+        while t != 1 {
+            let i = (1..m).find(|&i| pow_mod_u64(t, 1 << i, p) == 1).unwrap();
+            let b = pow_mod_u64(c, 1 << (m - i - 1), p);
+            r = r * b % p;
+            c = b * b % p;
+            t = t * c % p;
+            m = i;
+        }
+        r
+    };
+
+    for p in [17u64, 41, 73, 97, 193, 257, 337, 7681] {
+        let non_residues: Vec<u64> = (2..p)
+            .filter(|&z| legendre_symbol_u64(z, p) == -1)
+            .collect();
+        for n in (1..p).filter(|&n| legendre_symbol_u64(n, p) == 1) {
+            let expected = modular_sqrt(n, p).unwrap();
+            assert!(expected <= p - expected);
+
+            let raw: Vec<u64> = non_residues
+                .iter()
+                .map(|&z| tonelli_shanks_from(n, p, z))
+                .collect();
+            for &r in &raw {
+                assert!(r == expected || r == p - expected);
+            }
+        }
+        // Some non-residues really do lead to the larger root
+        assert!((1..p).any(|n| {
+            legendre_symbol_u64(n, p) == 1
+                && non_residues
+                    .iter()
+                    .any(|&z| tonelli_shanks_from(n, p, z) != modular_sqrt(n, p).unwrap())
+        }));
+    }
+
+    assert_eq!(modular_sqrt(0, 17), Some(0));
+    assert_eq!(modular_sqrt(1, 2), Some(1));
+    assert_eq!(modular_sqrt(3, 17), None);
+}
+
+#[test]
+fn test_is_prime() {
+    let trial_division = |n: u64| {
+        n >= 2
+            && (2..)
+                .take_while(|d| d * d <= n)
+                .all(|d| !n.is_multiple_of(d))
+    };
+    for n in 0..10000 {
+        assert_eq!(is_prime(n), trial_division(n), "n = {n}");
+    }
+
+    // Strong pseudoprimes to many small bases
+    assert!(!is_prime(3215031751)); // bases 2, 3, 5, 7
+    assert!(!is_prime(3825123056546413051)); // bases 2 through 31
+
+    assert!(is_prime(4294967291));
+    assert!(is_prime(18446744073709551557));
+    assert!(is_prime(18446744069414584321));
+    assert!(!is_prime(u64::MAX));
+    assert!(!is_prime(4294967297)); // 641 · 6700417
+}
+
+#[test]
+fn test_is_prime_with_witnesses() {
+    // Small primes and composites are decided by base 2 alone
+    for p in [2, 3, 5, 7, 11, 13, 97, 1009] {
+        assert!(is_prime_with_witnesses(p, &[2]));
+    }
+    for n in [0, 1, 4, 9, 15, 21, 91, 1001] {
+        assert!(!is_prime_with_witnesses(n, &[2]));
+    }
+
+    // Strong pseudoprimes pass the bases they fool and fail once another is added
+    assert!(is_prime_with_witnesses(2047, &[2]));
+    assert!(!is_prime_with_witnesses(2047, &[2, 3]));
+    assert!(is_prime_with_witnesses(3215031751, &[2, 3, 5, 7]));
+    assert!(!is_prime_with_witnesses(3215031751, &[2, 3, 5, 7, 11]));
+    assert!(is_prime_with_witnesses(
+        3825123056546413051,
+        &[2, 3, 5, 7, 11, 13, 17, 19, 23]
+    ));
+    assert!(!is_prime_with_witnesses(
+        3825123056546413051,
+        &PRIME_WITNESSES
+    ));
+
+    // No bases, and bases that are multiples of n, prove nothing
+    assert!(is_prime_with_witnesses(1001, &[]));
+    assert!(is_prime_with_witnesses(7, &[7, 14, 2]));
+}
+
+#[test]
+fn test_root_gap() {
+    assert_eq!(root_gap(2, 7), Some(1));
+    assert_eq!(root_gap(0, 7), Some(0));
+    assert_eq!(root_gap(14, 7), Some(0));
+    assert_eq!(root_gap(1, 2), Some(0));
+    assert_eq!(root_gap(3, 7), None);
+
+    for p in [3, 5, 13, 17, 97, 1009, 18446744073709551557] {
+        for n in (0..200).chain([p - 1]) {
+            match tonelli_shanks_u64(n, p) {
+                Some(r) => {
+                    let gap = root_gap(n, p).unwrap();
+                    assert_eq!(gap, (p - r).abs_diff(r) % p);
+                    // p - 2r is odd for odd p, so only n ≡ 0 has no gap
+                    assert_eq!(gap == 0, n % p == 0);
+                }
+                None => assert_eq!(root_gap(n, p), None),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ntt_root_of_unity() {
+    assert_eq!(ntt_root_of_unity(998244353, 23), Some(15311432));
+    assert_eq!(ntt_root_of_unity(998244353, 24), None);
+    assert_eq!(ntt_root_of_unity(7, 1), Some(6));
+    assert_eq!(ntt_root_of_unity(7, 2), None);
+    assert_eq!(ntt_root_of_unity(2, 0), Some(1));
+    assert_eq!(ntt_root_of_unity(2, 1), None);
+    assert_eq!(ntt_root_of_unity(1, 0), None);
+
+    for p in [17u64, 97, 257, 65537, 7340033, 998244353] {
+        for log_n in 0..=(p - 1).trailing_zeros() {
+            let w = ntt_root_of_unity(p, log_n).unwrap();
+            assert_eq!(pow_mod_u64(w, 1 << log_n, p), 1);
+            if log_n > 0 {
+                assert_eq!(pow_mod_u64(w, 1 << (log_n - 1), p), p - 1);
+            } else {
+                assert_eq!(w, 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_roots_structured() {
+    let roots = roots_structured(4, 45);
+    assert_eq!(roots.per_prime, vec![(9, vec![2, 7]), (5, vec![2, 3])]);
+    assert_eq!(roots.combined, vec![2, 7, 38, 43]);
+
+    assert_eq!(roots_structured(5, 1).per_prime, vec![]);
+    assert_eq!(roots_structured(5, 1).combined, vec![0]);
+
+    for m in 1..300 {
+        for n in 0..m {
+            let roots = roots_structured(n, m);
+            assert_eq!(roots.combined, all_square_roots(n, m));
+            assert_eq!(roots.combined.first().copied(), modular_sqrt_any(n, m));
+
+            assert_eq!(
+                roots.per_prime.iter().map(|(q_e, _)| q_e).product::<u64>(),
+                m
+            );
+            for (q_e, residues) in &roots.per_prime {
+                assert_eq!(residues, &all_square_roots(n, *q_e));
+            }
+            assert_eq!(
+                roots
+                    .per_prime
+                    .iter()
+                    .map(|(_, r)| r.len())
+                    .product::<usize>(),
+                roots.combined.len()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_modular_sqrt_fast_s() {
+    assert_eq!(modular_sqrt_fast_s(2, 17), Some(6));
+    assert_eq!(modular_sqrt_fast_s(3, 17), None);
+    assert_eq!(modular_sqrt_fast_s(0, 17), Some(0));
+    assert_eq!(modular_sqrt_fast_s(1, 2), Some(1));
+
+    // Every residue of primes with both small and large 2-adic valuation
+    for p in [3u64, 5, 7, 13, 17, 41, 97, 257, 7681, 12289, 40961, 65537] {
+        for n in 0..p {
+            assert_eq!(
+                modular_sqrt_fast_s(n, p),
+                tonelli_shanks_u64(n, p),
+                "n = {n}, p = {p}"
+            );
+        }
+    }
+
+    // p - 1 = 2^s · k with s = 23, 27, 30, 32, 40, 48 and 56
+    let large_s = [
+        998244353u64,
+        2013265921,
+        3221225473,
+        18446744069414584321,
+        29686813949953,
+        4222124650659841,
+        1945555039024054273,
+    ];
+    for p in large_s {
+        for n in (0..300).chain([p - 1, p - 2, u64::MAX]) {
+            assert_eq!(
+                modular_sqrt_fast_s(n, p),
+                tonelli_shanks_u64(n, p),
+                "n = {n}, p = {p}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_modular_sqrt_fast_s_opcount() {
+    // On the largest-s primes the table-driven version needs fewer
+    // operations than the classic loop, summed over many inputs
+    for p in [
+        18446744069414584321u64,
+        4222124650659841,
+        1945555039024054273,
+    ] {
+        let (mut fast_total, mut naive_total) = (0, 0);
+        for n in (2..2000).filter(|&n| legendre_symbol_u64(n, p) == 1) {
+            let (root, fast) = modular_sqrt_fast_s_opcount(n, p);
+            let (expected, naive) = modular_sqrt_opcount(n, p);
+            assert_eq!(root, expected);
+            assert_eq!(fast.invs, 0);
+            fast_total += fast.mults + fast.sqrs;
+            naive_total += naive.mults + naive.sqrs;
+        }
+        assert!(
+            fast_total < naive_total,
+            "p = {p}: {fast_total} vs {naive_total}"
+        );
+    }
+}
+
+#[test]
+fn test_prime_field_validate() {
+    for p in [
+        2,
+        3,
+        5,
+        7,
+        13,
+        17,
+        97,
+        65537,
+        998244353,
+        18446744069414584321,
+    ] {
+        assert_eq!(PrimeField::new(p).validate(), Ok(()));
+    }
+
+    for p in [3, 5, 13, 17, 41, 97] {
+        for z in 1..p {
+            if legendre_symbol(z, p) == -1 {
+                let field = PrimeField::from_parts(p, z).unwrap();
+                assert_eq!(field.validate(), Ok(()));
+                for n in 0..p {
+                    assert_eq!(field.sqrt(n), tonelli_shanks(n, p));
+                }
+            } else {
+                assert_eq!(
+                    PrimeField::from_parts(p, z),
+                    Err(TonelliError::InvalidField)
+                );
+                let corrupted = PrimeField::from_parts_unchecked(p, z);
+                assert_eq!(corrupted.validate(), Err(TonelliError::InvalidField));
+            }
+        }
+    }
+    assert_eq!(PrimeField::from_parts(17, 3), Ok(PrimeField::new(17)));
+
+    assert_eq!(PrimeField::from_parts(2, 5), Ok(PrimeField::new(2)));
+    assert_eq!(PrimeField::from_parts(0, 3), Err(TonelliError::ModulusZero));
+    assert_eq!(PrimeField::from_parts(8, 3), Err(TonelliError::ModulusEven));
+    assert_eq!(
+        PrimeField::from_parts(1, 0),
+        Err(TonelliError::InvalidField)
+    );
+}
+
+#[test]
+fn test_collect_smooth_relations() {
+    assert_eq!(
+        collect_smooth_relations(15347, &[2, 17, 23, 29], 100),
+        vec![
+            (124, vec![0, 0, 0, 1]),
+            (126, vec![0, 0, 2, 0]),
+            (127, vec![1, 1, 1, 0]),
+            (195, vec![1, 1, 1, 1]),
+        ]
+    );
+
+    // Against trial division of every sieved value, with primes modulo which
+    // n is not a residue, a prime dividing n and unsorted bases
+    for (n, factor_base) in [
+        (87463u64, vec![2, 3, 13, 17, 19, 29]),
+        (87463, vec![29, 19, 17, 13, 3, 2, 5, 7, 11]),
+        (
+            1000009,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47],
+        ),
+        (10403, vec![2, 3, 5, 7, 101, 103]),
+    ] {
+        let start = (1..).find(|a| a * a >= n).unwrap();
+        let expected: Vec<(u64, Vec<u32>)> = (start..start + 2000)
+            .filter_map(|a| {
+                let mut value = a * a - n;
+                if value == 0 {
+                    return None;
+                }
+                let exponents = factor_base
+                    .iter()
+                    .map(|&p| {
+                        let mut e = 0;
+                        while value.is_multiple_of(p) {
+                            value /= p;
+                            e += 1;
+                        }
+                        e
+                    })
+                    .collect();
+                (value == 1).then_some((a, exponents))
+            })
+            .collect();
+
+        let relations = collect_smooth_relations(n, &factor_base, 2000);
+        assert!(!relations.is_empty());
+        assert_eq!(relations, expected, "n = {n}");
+    }
+
+    // 10000 = 100², so Q(100) = 0 is skipped
+    let relations = collect_smooth_relations(10000, &[2, 3, 5, 7], 50);
+    assert!(relations.iter().all(|&(a, _)| a > 100));
+    assert_eq!(relations[0], (110, vec![2, 1, 2, 1])); // 2100 = 2² · 3 · 5² · 7
+}
+
+#[test]
+#[should_panic]
+fn test_collect_smooth_relations_rejects_one() {
+    collect_smooth_relations(15347, &[2, 1], 5);
+}
+
+#[test]
+fn test_sqrt_const() {
+    const ROOT: Option<u64> = sqrt_const::<7>(2);
+    const NONE: Option<u64> = sqrt_const::<7>(3);
+    const LARGE: Option<u64> = sqrt_const::<998244353>(2);
+    assert_eq!(ROOT, Some(3));
+    assert_eq!(NONE, None);
+    assert_eq!(LARGE, tonelli_shanks(2, 998244353));
+
+    /// Checks every n below 300, and a few large ones, against `tonelli_shanks`
+    fn check<const P: u64>() {
+        for n in (0..300).chain([P - 1, P, u64::MAX]) {
+            assert_eq!(
+                sqrt_const::<P>(n),
+                tonelli_shanks_u64(n, P),
+                "n = {n}, P = {P}"
+            );
+        }
+    }
+    check::<2>();
+    check::<3>();
+    check::<5>();
+    check::<17>();
+    check::<97>();
+    check::<257>();
+    check::<65537>();
+    check::<7340033>();
+    check::<998244353>();
+    check::<18446744069414584321>();
+    check::<18446744073709551557>();
+}
@@ -0,0 +1,115 @@
+// Core tests written against `Int`, so they compile and pass under both the
+// `width-u64` and `width-u128` features.
+use tonelli_rs::*;
+
+#[test]
+fn test_int_width() {
+    #[cfg(not(feature = "width-u128"))]
+    assert_eq!(std::mem::size_of::<Int>(), 8);
+    #[cfg(feature = "width-u128")]
+    assert_eq!(std::mem::size_of::<Int>(), 16);
+}
+
+#[test]
+fn test_core_functions_at_int_width() {
+    let p: Int = 17;
+    assert_eq!(pow_mod(3, 5, 7), Ok(5));
+    assert_eq!(legendre_symbol(2, p), Ok(1));
+    assert_eq!(legendre_symbol(3, p), Ok(-1));
+    assert_eq!(tonelli_shanks(2, p), Ok(Some(6)));
+    assert_eq!(tonelli_shanks(3, p), Ok(None));
+    assert_eq!(square_roots(2, p), Ok(Some((6, 11))));
+    assert_eq!(square_roots(0, p), Ok(Some((0, 0))));
+
+    for p in [3, 5, 7, 13, 17, 97, 257] as [Int; 7] {
+        for n in 0..p {
+            match square_roots(n, p).unwrap() {
+                Some((r1, r2)) => {
+                    assert_eq!(pow_mod(r1, 2, p), Ok(n));
+                    assert_eq!(pow_mod(r2, 2, p), Ok(n));
+                }
+                None => assert_eq!(legendre_symbol(n, p), Ok(-1)),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_core_functions_report_invalid_moduli() {
+    assert_eq!(pow_mod(3, 5, 0), Err(TonelliError::ModulusZero));
+    assert_eq!(legendre_symbol(3, 0), Err(TonelliError::ModulusZero));
+    assert_eq!(tonelli_shanks(2, 8), Err(TonelliError::ModulusEven));
+    assert_eq!(square_roots(2, 8), Err(TonelliError::ModulusEven));
+    assert_eq!(tonelli_shanks(1, 2), Ok(Some(1)));
+
+    let large: Int = 1 << 40;
+    assert_eq!(tonelli_shanks(4, large), Err(TonelliError::ModulusEven));
+}
+
+#[test]
+fn test_u64_and_u128_versions_agree() {
+    for p in [3u64, 5, 7, 13, 17, 97, 257, 65537, 998244353] {
+        for n in 0..p.min(300) {
+            let wide = tonelli_shanks_u128(n as u128, p as u128);
+            assert_eq!(wide, tonelli_shanks_u64(n, p).map(u128::from));
+            assert_eq!(
+                legendre_symbol_u128(n as u128, p as u128),
+                legendre_symbol_u64(n, p)
+            );
+            assert_eq!(
+                square_roots_u128(n as u128, p as u128),
+                square_roots_u64(n, p).map(|(a, b)| (a as u128, b as u128))
+            );
+        }
+    }
+}
+
+#[test]
+fn test_u128_large_primes() {
+    // 2^127 - 1, 25 · 2^64 + 1 and 165 · 2^100 + 1 are all prime
+    for p in [(1u128 << 127) - 1, (25u128 << 64) + 1, (165u128 << 100) + 1] {
+        for n in [2u128, 3, 5, 123456789, p - 1, p - 2] {
+            match tonelli_shanks_u128(n, p) {
+                Some(r) => {
+                    assert!(r <= p - r);
+                    assert_eq!(pow_mod_u128(r, 2, p), n % p);
+                }
+                None => assert_eq!(legendre_symbol_u128(n, p), -1),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_u32_u64_u128_agree() {
+    let is_prime = |p: u64| {
+        p >= 2
+            && (2..)
+                .take_while(|d| d * d <= p)
+                .all(|d| !p.is_multiple_of(d))
+    };
+
+    // Every prime below 5000, plus primes with large s and primes near 2^32
+    let primes = (2..5000)
+        .chain([65537, 786433, 7340033, 998244353, 2013265921, 3221225473])
+        .chain((1u64 << 32) - 1000..1 << 32)
+        .filter(|&p| is_prime(p));
+
+    for p in primes {
+        let ns = (0..200).chain([p - 1, p, p + 1, u32::MAX as u64]);
+        for n in ns {
+            let n32 = (n % (1 << 32)) as u32;
+            let narrow = tonelli_shanks_u32(n32, p as u32).map(u64::from);
+            let regular = tonelli_shanks_u64(n32 as u64, p);
+            let wide = tonelli_shanks_u128(n32 as u128, p as u128).map(|r| r as u64);
+            assert_eq!(
+                narrow, regular,
+                "u32 and u64 disagree for n = {n32}, p = {p}"
+            );
+            assert_eq!(
+                wide, regular,
+                "u128 and u64 disagree for n = {n32}, p = {p}"
+            );
+        }
+    }
+}